@@ -0,0 +1,192 @@
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+use crate::*;
+
+/// A search for an ambiguous sentence of a `Grammar`.
+///
+/// A conflict-free LR(1) table proves a grammar unambiguous, but a conflict proves nothing.
+/// Ambiguity is undecidable in general, so the search is limited by sentence length and, optionally, time.
+///
+/// The search builds every parse tree for every string of up to `max_length` terminals,
+/// shortest first, keeping one tree for each symbol and string.
+/// When a second tree turns up for the same symbol and string,
+/// it is placed in a shortest sentence around that symbol to make the witness.
+pub struct AmbiguitySearch<'g> {
+    grammar: &'g Grammar,
+    max_length: usize,
+    time_limit: Option<Duration>,
+}
+
+/// The result of an `AmbiguitySearch`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Ambiguity<'g> {
+    /// A sentence with two different parse trees.
+    Found {
+        sentence: Vec<Symbol<'g>>,
+        trees: (ParseTree<'g>, ParseTree<'g>),
+    },
+
+    /// Every sentence of up to `up_to` terminals has at most one parse tree.
+    ///
+    /// This is less than the maximum length when the time limit ran out,
+    /// and `None` when it ran out before the empty sentence was checked.
+    NotFound {
+        up_to: Option<usize>,
+    },
+}
+
+impl<'g> AmbiguitySearch<'g> {
+    pub fn new(grammar: &'g Grammar) -> AmbiguitySearch<'g> {
+        AmbiguitySearch {
+            grammar,
+            max_length: 8,
+            time_limit: None,
+        }
+    }
+
+    /// Limit the length of the sentences searched. The default is 8.
+    pub fn max_length(mut self, max_length: usize) -> Self {
+        self.max_length = max_length;
+        self
+    }
+
+    /// Stop searching after `time_limit`. By default, there is no limit.
+    pub fn time_limit(mut self, time_limit: Duration) -> Self {
+        self.time_limit = Some(time_limit);
+        self
+    }
+
+    pub fn grammar(&self) -> &'g Grammar {
+        self.grammar
+    }
+
+    /// Run the search.
+    pub fn run(&self) -> Ambiguity<'g> {
+        let deadline = self.time_limit.map(|time_limit| Instant::now() + time_limit);
+        let analysis = GrammarAnalysis::build(self.grammar);
+        let contexts = analysis.shortest_contexts();
+
+        let mut trees: BTreeMap<Symbol<'g>, Vec<BTreeMap<Vec<Symbol<'g>>, ParseTree<'g>>>> = BTreeMap::new();
+        for symbol in self.grammar.symbols() {
+            let mut by_length = vec![BTreeMap::new(); self.max_length + 1];
+            if symbol.is_terminal() && self.max_length > 0 {
+                by_length[1].insert(vec![symbol], ParseTree::Token(symbol));
+            }
+            trees.insert(symbol, by_length);
+        }
+
+        let mut up_to = None;
+        for length in 0..=self.max_length {
+            // Trees of each length can contain trees of the same length
+            // through nullable symbols, so each length is iterated to a fixpoint.
+            let mut changed = true;
+            while changed {
+                changed = false;
+                for rule in self.grammar.rules() {
+                    if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                        return Ambiguity::NotFound { up_to };
+                    }
+
+                    let mut found = vec![];
+                    combinations(&trees, &rule.rhs(), length, &mut vec![], &mut vec![], &mut found);
+
+                    for (sentence, children) in found {
+                        let tree = ParseTree::Node(rule, children);
+                        let lhs_trees = &mut trees.get_mut(&rule.lhs()).unwrap()[length];
+                        match lhs_trees.get(&sentence) {
+                            None => {
+                                lhs_trees.insert(sentence, tree);
+                                changed = true;
+                            }
+                            Some(existing) if *existing != tree => {
+                                let fits = context_length(&analysis, &contexts, rule.lhs())
+                                    .is_some_and(|context_length| context_length + length <= self.max_length);
+                                if fits {
+                                    let first = in_context(&analysis, &contexts, existing.clone());
+                                    let second = in_context(&analysis, &contexts, tree);
+                                    return Ambiguity::Found {
+                                        sentence: first.leaves(),
+                                        trees: (first, second),
+                                    };
+                                }
+                            }
+                            Some(_existing) => (),
+                        }
+                    }
+                }
+            }
+            up_to = Some(length);
+        }
+
+        Ambiguity::NotFound { up_to }
+    }
+}
+
+// Add every way of choosing a tree for each symbol of `rhs`,
+// with a total of `length` terminals, to `found`, together with the string of terminals.
+fn combinations<'g>(
+    trees: &BTreeMap<Symbol<'g>, Vec<BTreeMap<Vec<Symbol<'g>>, ParseTree<'g>>>>,
+    rhs: &[Symbol<'g>],
+    length: usize,
+    sentence: &mut Vec<Symbol<'g>>,
+    children: &mut Vec<ParseTree<'g>>,
+    found: &mut Vec<(Vec<Symbol<'g>>, Vec<ParseTree<'g>>)>,
+) {
+    let Some((first, rest)) = rhs.split_first() else {
+        if length == 0 {
+            found.push((sentence.clone(), children.clone()));
+        }
+        return;
+    };
+
+    for first_length in 0..=length {
+        for (yielded, tree) in &trees[first][first_length] {
+            let sentence_length = sentence.len();
+            sentence.extend(yielded);
+            children.push(tree.clone());
+            combinations(trees, rest, length - first_length, sentence, children, found);
+            children.pop();
+            sentence.truncate(sentence_length);
+        }
+    }
+}
+
+// The number of terminals around `symbol` in a shortest sentence which uses it,
+// or `None` if no sentence uses it.
+fn context_length<'g>(
+    analysis: &GrammarAnalysis<'g>,
+    contexts: &BTreeMap<Symbol<'g>, (Rule<'g>, usize)>,
+    symbol: Symbol<'g>,
+) -> Option<usize> {
+    let start_symbol = analysis.grammar().start_symbol();
+    let mut length = 0;
+    let mut symbol = symbol;
+    while symbol != start_symbol {
+        let (rule, position) = contexts.get(&symbol)?;
+        for (i, sibling) in rule.rhs().into_iter().enumerate() {
+            if i != *position {
+                length += analysis.shortest_yield(sibling).len();
+            }
+        }
+        symbol = rule.lhs();
+    }
+    Some(length)
+}
+
+// Extend `tree` to a parse tree for a shortest sentence around its root symbol.
+fn in_context<'g>(
+    analysis: &GrammarAnalysis<'g>,
+    contexts: &BTreeMap<Symbol<'g>, (Rule<'g>, usize)>,
+    tree: ParseTree<'g>,
+) -> ParseTree<'g> {
+    let start_symbol = analysis.grammar().start_symbol();
+    let mut tree = tree;
+    while tree.symbol() != start_symbol {
+        let (rule, position) = contexts[&tree.symbol()];
+        let mut children: Vec<ParseTree<'g>> = rule.rhs().into_iter().map(|symbol| analysis.shortest_derivation(symbol)).collect();
+        children[position] = tree;
+        tree = ParseTree::Node(rule, children);
+    }
+    tree
+}
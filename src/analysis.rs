@@ -1,27 +1,70 @@
+mod dependency;
+mod explain;
+mod properties;
+mod approximation;
+
 use std::collections::{BTreeMap, BTreeSet};
 
 use super::*;
 
+pub use dependency::{DependencyGraph, Recursion, RecursionKind};
+
 /// A structure for calculating the set of nullable nonterminals
 /// as well as the FIRST and FOLLOW sets for each nonterminal.
 pub struct GrammarAnalysis<'g> {
+    grammar: &'g Grammar,
     nullables: BTreeSet<Symbol<'g>>,
+    productives: BTreeSet<Symbol<'g>>,
+    reachables: BTreeSet<Symbol<'g>>,
+    shortest_rules: BTreeMap<Symbol<'g>, Rule<'g>>,
     first_follows: FirstFollows<'g>,
+    k: usize,
+    first_ks: BTreeMap<Symbol<'g>, BTreeSet<Vec<Symbol<'g>>>>,
+    follow_ks: BTreeMap<Symbol<'g>, BTreeSet<Vec<Option<Symbol<'g>>>>>,
 }
 
 impl<'g> GrammarAnalysis<'g> {
     /// The constructor for `GrammarAnalysis`.
     /// This builds the nullable set and the FIRST and FOLLOW sets for `grammar`.
     pub fn build(grammar: &'g Grammar) -> GrammarAnalysis<'g> {
+        Self::build_k(grammar, 1)
+    }
+
+    /// Like `build`, but additionally calculates the FIRST_k and FOLLOW_k sets for `grammar`.
+    ///
+    /// See `first_k` and `follow_k`.
+    pub fn build_k(grammar: &'g Grammar, k: usize) -> GrammarAnalysis<'g> {
+        assert!(k > 0, "Lookahead must be at least 1");
         let nullables = Self::calc_nullables(grammar);
+        let productives = Self::calc_productives(grammar);
+        let reachables = Self::calc_reachables(grammar);
+        let shortest_rules = Self::calc_shortest_rules(grammar);
         let first_follows = Self::calc_first_follows(grammar, &nullables);
+        let first_ks = Self::calc_first_ks(grammar, &nullables, k);
+        let follow_ks = Self::calc_follow_ks(grammar, &first_ks, k);
 
         GrammarAnalysis {
+            grammar,
             nullables,
+            productives,
+            reachables,
+            shortest_rules,
             first_follows,
+            k,
+            first_ks,
+            follow_ks,
         }
     }
 
+    pub fn grammar(&self) -> &'g Grammar {
+        self.grammar
+    }
+
+    /// The amount of lookahead used for `first_k` and `follow_k`.
+    pub fn k(&self) -> usize {
+        self.k
+    }
+
     /// Returns the set of nullable symbols.
     ///
     /// The result is a set of nonterminals.
@@ -36,6 +79,106 @@ impl<'g> GrammarAnalysis<'g> {
         self.nullables.contains(&symbol)
     }
 
+    /// Returns the set of unproductive nonterminals.
+    ///
+    /// A nonterminal is productive if it can expand into some string of terminals.
+    /// An unproductive nonterminal can never appear in a parse tree.
+    pub fn unproductive(&self) -> BTreeSet<Symbol<'g>> {
+        self.grammar.nonterminals().into_iter().filter(|symbol| !self.productives.contains(symbol)).collect()
+    }
+
+    /// Returns the set of nonterminals which can't be reached from the start symbol.
+    pub fn unreachable(&self) -> BTreeSet<Symbol<'g>> {
+        self.grammar.nonterminals().into_iter().filter(|symbol| !self.reachables.contains(symbol)).collect()
+    }
+
+    /// Returns the set of terminals which can't be reached from the start symbol.
+    ///
+    /// This includes the terminals which don't appear in any rule.
+    pub fn unused_terminals(&self) -> BTreeSet<Symbol<'g>> {
+        self.grammar.terminals().into_iter().filter(|symbol| !self.reachables.contains(symbol)).collect()
+    }
+
+    /// Returns a shortest string of terminals which `symbol` can expand into.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `symbol` is unproductive.
+    pub fn shortest_yield(&self, symbol: Symbol<'g>) -> Vec<Symbol<'g>> {
+        self.shortest_derivation(symbol).leaves()
+    }
+
+    /// Returns a parse tree for a shortest string of terminals which `symbol` can expand into.
+    ///
+    /// When there is more than one, the rules which come first in the grammar are preferred.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `symbol` is unproductive.
+    pub fn shortest_derivation(&self, symbol: Symbol<'g>) -> ParseTree<'g> {
+        if symbol.is_terminal() {
+            return ParseTree::Token(symbol);
+        }
+
+        let rule = self.shortest_rule(symbol);
+        let children = rule.rhs().into_iter().map(|symbol| self.shortest_derivation(symbol)).collect();
+        ParseTree::Node(rule, children)
+    }
+
+    /// The rule at the root of `shortest_derivation(symbol)`, for a nonterminal `symbol`.
+    pub(crate) fn shortest_rule(&self, symbol: Symbol<'g>) -> Rule<'g> {
+        match self.shortest_rules.get(&symbol) {
+            Some(rule) => *rule,
+            None => panic!("Symbol is unproductive: {symbol:?}"),
+        }
+    }
+
+    /// For each nonterminal which appears in a parse tree for some sentence,
+    /// the rule and position through which it is reached in a shortest such sentence.
+    ///
+    /// Following these back from a nonterminal leads to the start symbol.
+    /// This is the second phase of Purdom's algorithm.
+    pub(crate) fn shortest_contexts(&self) -> BTreeMap<Symbol<'g>, (Rule<'g>, usize)> {
+        let start_symbol = self.grammar.start_symbol();
+        let mut contexts = BTreeMap::new();
+        if !self.productives.contains(&start_symbol) {
+            return contexts;
+        }
+
+        let min_lengths: BTreeMap<Symbol<'g>, usize> = self
+            .productives
+            .iter()
+            .map(|symbol| (*symbol, self.shortest_yield(*symbol).len()))
+            .collect();
+        let min_length = |symbol: &Symbol<'g>| min_lengths[symbol];
+
+        // The length of the shortest sentence using each nonterminal,
+        // found by relaxing the rules until nothing improves.
+        let mut lengths = BTreeMap::from([(start_symbol, min_length(&start_symbol))]);
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for rule in self.grammar.rules() {
+                let Some(lhs_length) = lengths.get(&rule.lhs()).copied() else {
+                    continue;
+                };
+                if !rule.rhs().iter().all(|symbol| self.productives.contains(symbol)) {
+                    continue;
+                }
+
+                let length = lhs_length - min_length(&rule.lhs()) + rule.rhs().iter().map(min_length).sum::<usize>();
+                for (position, symbol) in rule.rhs().into_iter().enumerate() {
+                    if symbol.is_nonterminal() && lengths.get(&symbol).is_none_or(|old| length < *old) {
+                        lengths.insert(symbol, length);
+                        contexts.insert(symbol, (rule, position));
+                        changed = true;
+                    }
+                }
+            }
+        }
+        contexts
+    }
+
     pub fn first_seq(&self, seq: &[Symbol<'g>]) -> BTreeSet<Symbol<'g>> {
         let mut result = BTreeSet::new();
 
@@ -79,6 +222,33 @@ impl<'g> GrammarAnalysis<'g> {
         self.first_follows.follows_to_follow(start_symbol).contains(&symbol)
     }
 
+    /// Returns the FIRST_k set for a sequence of symbols.
+    ///
+    /// The result is a set of strings of terminals.
+    /// Each string is a prefix of length `k` of some string of terminals the sequence can expand into.
+    /// A string is shorter than `k` only when the whole expansion is shorter than `k`.
+    pub fn first_k(&self, seq: &[Symbol<'g>]) -> BTreeSet<Vec<Symbol<'g>>> {
+        let mut result: BTreeSet<Vec<Symbol<'g>>> = [vec![]].into_iter().collect();
+        for symbol in seq {
+            // Once every string is full, the rest of the sequence can't change the result.
+            if result.iter().all(|string| string.len() == self.k) {
+                break;
+            }
+            result = concat_k(&result, &self.first_ks[symbol], self.k);
+        }
+        result
+    }
+
+    /// Returns the FOLLOW_k set for a nonterminal `Symbol`.
+    ///
+    /// The result is a set of strings of terminals, each of length at most `k`.
+    /// A string is in the FOLLOW_k set if it could legally follow the nonterminal during parsing.
+    /// When the end of the input could be reached before `k` terminals are seen,
+    /// the string is terminated with `None`, which represents EOF.
+    pub fn follow_k(&self, symbol: Symbol<'g>) -> BTreeSet<Vec<Option<Symbol<'g>>>> {
+        self.follow_ks.get(&symbol).cloned().unwrap_or_default()
+    }
+
     fn calc_nullables(grammar: &'g Grammar) -> BTreeSet<Symbol<'g>> {
         let mut nullables = BTreeSet::new();
 
@@ -106,6 +276,167 @@ impl<'g> GrammarAnalysis<'g> {
         nullables
     }
 
+    fn calc_productives(grammar: &'g Grammar) -> BTreeSet<Symbol<'g>> {
+        let mut productives: BTreeSet<Symbol<'g>> = grammar.terminals().into_iter().collect();
+
+        loop {
+            let mut dirty = false;
+
+            for rule in grammar.rules() {
+                if !productives.contains(&rule.lhs()) && rule.rhs().iter().all(|symbol| productives.contains(symbol)) {
+                    productives.insert(rule.lhs());
+                    dirty = true;
+                }
+            }
+
+            if !dirty {
+                break;
+            }
+        }
+
+        productives
+    }
+
+    // Knuth's generalization of Dijkstra's algorithm.
+    // Each step settles the nonterminal with the shortest yield among the rules whose RHS is already settled.
+    // The length of a yield is the sum of the lengths of the yields on the RHS,
+    // so no nonterminal settled later can have a shorter yield.
+    fn calc_shortest_rules(grammar: &'g Grammar) -> BTreeMap<Symbol<'g>, Rule<'g>> {
+        let mut lengths: BTreeMap<Symbol<'g>, usize> = grammar.terminals().into_iter().map(|symbol| (symbol, 1)).collect();
+        let mut shortest_rules = BTreeMap::new();
+
+        loop {
+            let mut best: Option<(usize, Rule<'g>)> = None;
+
+            for rule in grammar.rules() {
+                if lengths.contains_key(&rule.lhs()) {
+                    continue;
+                }
+
+                let length: Option<usize> = rule.rhs().iter().map(|symbol| lengths.get(symbol).copied()).sum();
+                if let Some(length) = length && best.is_none_or(|(best_length, _rule)| length < best_length) {
+                    best = Some((length, rule));
+                }
+            }
+
+            match best {
+                Some((length, rule)) => {
+                    lengths.insert(rule.lhs(), length);
+                    shortest_rules.insert(rule.lhs(), rule);
+                }
+                None => break,
+            }
+        }
+
+        shortest_rules
+    }
+
+    fn calc_reachables(grammar: &'g Grammar) -> BTreeSet<Symbol<'g>> {
+        let mut reachables = BTreeSet::new();
+        let mut queue = vec![grammar.start_symbol()];
+
+        while let Some(symbol) = queue.pop() {
+            if reachables.insert(symbol) {
+                for rule in symbol.rules() {
+                    queue.extend(rule.rhs());
+                }
+            }
+        }
+
+        reachables
+    }
+
+    // Calculate FIRST_k for every symbol by iterating to a fixpoint.
+    // Terminals start with the singleton string.
+    // Nonterminals start with the empty string if they are nullable.
+    fn calc_first_ks(
+        grammar: &'g Grammar,
+        nullables: &BTreeSet<Symbol<'g>>,
+        k: usize,
+    ) -> BTreeMap<Symbol<'g>, BTreeSet<Vec<Symbol<'g>>>> {
+        let mut first_ks = BTreeMap::new();
+        for symbol in grammar.symbols() {
+            let mut first_k = BTreeSet::new();
+            if symbol.is_terminal() {
+                first_k.insert(vec![symbol]);
+            } else if nullables.contains(&symbol) {
+                first_k.insert(vec![]);
+            }
+            first_ks.insert(symbol, first_k);
+        }
+
+        loop {
+            let mut dirty = false;
+
+            for rule in grammar.rules() {
+                let mut strings: BTreeSet<Vec<Symbol<'g>>> = [vec![]].into_iter().collect();
+                for symbol in rule.rhs() {
+                    strings = concat_k(&strings, &first_ks[&symbol], k);
+                }
+
+                let first_k = first_ks.get_mut(&rule.lhs()).unwrap();
+                for string in strings {
+                    dirty |= first_k.insert(string);
+                }
+            }
+
+            if !dirty {
+                break;
+            }
+        }
+
+        first_ks
+    }
+
+    // Calculate FOLLOW_k for every nonterminal by iterating to a fixpoint.
+    // For each rule `A -> α B β`, FOLLOW_k(B) contains FIRST_k(β) followed by FOLLOW_k(A).
+    fn calc_follow_ks(
+        grammar: &'g Grammar,
+        first_ks: &BTreeMap<Symbol<'g>, BTreeSet<Vec<Symbol<'g>>>>,
+        k: usize,
+    ) -> BTreeMap<Symbol<'g>, BTreeSet<Vec<Option<Symbol<'g>>>>> {
+        let mut follow_ks: BTreeMap<Symbol<'g>, BTreeSet<Vec<Option<Symbol<'g>>>>> = BTreeMap::new();
+        for symbol in grammar.nonterminals() {
+            follow_ks.insert(symbol, BTreeSet::new());
+        }
+        follow_ks.get_mut(&grammar.start_symbol()).unwrap().insert(vec![None]);
+
+        loop {
+            let mut dirty = false;
+
+            for rule in grammar.rules() {
+                let rhs = rule.rhs();
+                for (i, symbol) in rhs.iter().enumerate() {
+                    if symbol.is_terminal() {
+                        continue;
+                    }
+
+                    let mut strings: BTreeSet<Vec<Symbol<'g>>> = [vec![]].into_iter().collect();
+                    for follow in &rhs[i + 1..] {
+                        strings = concat_k(&strings, &first_ks[follow], k);
+                    }
+
+                    let strings: BTreeSet<Vec<Option<Symbol<'g>>>> = strings
+                        .into_iter()
+                        .map(|string| string.into_iter().map(Some).collect())
+                        .collect();
+                    let strings = concat_k(&strings, &follow_ks[&rule.lhs()], k);
+
+                    let follow_k = follow_ks.get_mut(symbol).unwrap();
+                    for string in strings {
+                        dirty |= follow_k.insert(string);
+                    }
+                }
+            }
+
+            if !dirty {
+                break;
+            }
+        }
+
+        follow_ks
+    }
+
     // Calculate the FirstFollows graph of the `Grammar`
     fn calc_first_follows(grammar: &'g Grammar, nullables: &BTreeSet<Symbol<'g>>) -> FirstFollows<'g> {
         let mut first_follows = FirstFollows::new();
@@ -113,11 +444,12 @@ impl<'g> GrammarAnalysis<'g> {
         for rule in grammar.rules() {
             // Firsts are Firsts
             // Note: We iterate the rule's RHS until we hit a non-nullable symbol
-            for symbol in rule.rhs() {
+            for (i, symbol) in rule.rhs().into_iter().enumerate() {
+                let reason = Reason { rule, nullable: 0..i };
                 if symbol.is_terminal() {
-                    first_follows.link(FFNode::First(rule.lhs()), FFNode::Terminal(symbol));
+                    first_follows.link(FFNode::First(rule.lhs()), FFNode::Terminal(symbol), reason);
                 } else {
-                    first_follows.link(FFNode::First(rule.lhs()), FFNode::First(symbol));
+                    first_follows.link(FFNode::First(rule.lhs()), FFNode::First(symbol), reason);
                 }
 
                 // if we'ved reached the last nullable nonterminal, break early
@@ -132,11 +464,12 @@ impl<'g> GrammarAnalysis<'g> {
                 for j in i+1 .. rule.rhs().len() {
                     // ... is followed by `follow`...
                     let follow = rule.rhs()[j];
+                    let reason = Reason { rule, nullable: i+1 .. j };
 
                     if follow.is_terminal() {
-                        first_follows.link(FFNode::Follow(symbol), FFNode::Terminal(follow));
+                        first_follows.link(FFNode::Follow(symbol), FFNode::Terminal(follow), reason);
                     } else {
-                        first_follows.link(FFNode::Follow(symbol), FFNode::First(follow));
+                        first_follows.link(FFNode::Follow(symbol), FFNode::First(follow), reason);
                     }
 
                     // if we'ved reached the last nullable nonterminal, break early
@@ -148,9 +481,11 @@ impl<'g> GrammarAnalysis<'g> {
 
             // Follows can also be follows, though
             // Note: We iterate the rule's RHS in reverse until we hit a non-nullable symbol
-            for symbol in rule.rhs().into_iter().rev() {
+            let len = rule.rhs().len();
+            for (i, symbol) in rule.rhs().into_iter().enumerate().rev() {
                 if symbol.is_nonterminal() {
-                    first_follows.link(FFNode::Follow(symbol), FFNode::Follow(rule.lhs()));
+                    let reason = Reason { rule, nullable: i+1 .. len };
+                    first_follows.link(FFNode::Follow(symbol), FFNode::Follow(rule.lhs()), reason);
                 }
 
                 if !nullables.contains(&symbol) {
@@ -163,16 +498,68 @@ impl<'g> GrammarAnalysis<'g> {
     }
 }
 
+// The k-concatenation of two sets of strings.
+// Each string of `lefts` is extended with each string of `rights`, and the result is truncated to length `k`.
+// A string which is already full (or which ends in EOF) is left alone.
+pub(crate) fn concat_k<T: Ord + Clone + IsEof>(lefts: &BTreeSet<Vec<T>>, rights: &BTreeSet<Vec<T>>, k: usize) -> BTreeSet<Vec<T>> {
+    let mut result = BTreeSet::new();
+    for left in lefts {
+        if left.len() >= k || left.last().is_some_and(IsEof::is_eof) {
+            result.insert(left.clone());
+            continue;
+        }
+
+        for right in rights {
+            let mut string = left.clone();
+            for symbol in right {
+                if string.len() == k {
+                    break;
+                }
+                string.push(symbol.clone());
+            }
+            result.insert(string);
+        }
+    }
+    result
+}
+
+// The elements of a lookahead string.
+// Only `Option<Symbol>` can represent EOF, as `None`.
+pub(crate) trait IsEof {
+    fn is_eof(&self) -> bool;
+}
+
+impl<'g> IsEof for Symbol<'g> {
+    fn is_eof(&self) -> bool {
+        false
+    }
+}
+
+impl<'g> IsEof for Option<Symbol<'g>> {
+    fn is_eof(&self) -> bool {
+        self.is_none()
+    }
+}
+
 // A graph data structure which tracks containment information
 // for FIRST sets, FOLLOW sets, and temrinals
 struct FirstFollows<'g> {
     // An adjacency list mapping `from_node` to `to_node`.
     // This indicates that the set represented by `from_node` contains the set represented by `to_node`
-    edges: BTreeMap<FFNode<'g>, BTreeSet<FFNode<'g>>>,
+    // Each edge remembers the first rule which introduced it.
+    edges: BTreeMap<FFNode<'g>, BTreeMap<FFNode<'g>, Reason<'g>>>,
 
     rev_edges: BTreeMap<FFNode<'g>, BTreeSet<FFNode<'g>>>,
 }
 
+// Why an edge is in the `FirstFollows` graph.
+// The symbols of the rule's RHS in the range `nullable` must derive ε for the edge to hold.
+#[derive(Clone)]
+struct Reason<'g> {
+    rule: Rule<'g>,
+    nullable: std::ops::Range<usize>,
+}
+
 #[derive(Eq, PartialEq, Hash, Clone, Copy, PartialOrd, Ord)]
 enum FFNode<'g> {
     // The FIRST set of the symbol
@@ -193,11 +580,12 @@ impl<'g> FirstFollows<'g> {
 
     // Declare that the set represented by `from_node` contains the set represented by `to_node`.
     // Note: If the `from_node` is not present in the graph already, it allocates a new adjacency list for it.
-    fn link(&mut self, from_node: FFNode<'g>, to_node: FFNode<'g>) {
+    // If the edge is already present, the original `reason` is kept.
+    fn link(&mut self, from_node: FFNode<'g>, to_node: FFNode<'g>, reason: Reason<'g>) {
         if !self.edges.contains_key(&from_node) {
-            self.edges.insert(from_node, BTreeSet::new());
+            self.edges.insert(from_node, BTreeMap::new());
         }
-        self.edges.get_mut(&from_node).unwrap().insert(to_node);
+        self.edges.get_mut(&from_node).unwrap().entry(to_node).or_insert(reason);
 
         if !self.rev_edges.contains_key(&to_node) {
             self.rev_edges.insert(to_node, BTreeSet::new());
@@ -218,7 +606,7 @@ impl<'g> FirstFollows<'g> {
             if let FFNode::Terminal(symbol) = node {
                 terminals.insert(symbol);
             } else if self.edges.contains_key(&node) {
-                for next_node in &self.edges[&node] {
+                for next_node in self.edges[&node].keys() {
                     if !visited.contains(next_node) {
                         queue.push(*next_node);
                     }
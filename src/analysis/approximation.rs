@@ -0,0 +1,77 @@
+use std::collections::BTreeSet;
+
+use super::*;
+use super::properties::linearity;
+use crate::dfa::Dfa;
+use crate::nfa::{Nfa, StateIdx};
+use crate::transform::{Draft, Provenance};
+
+impl<'g> GrammarAnalysis<'g> {
+    /// An NFA whose language contains every sentence of the grammar,
+    /// using the Mohri–Nederhof transformation.
+    ///
+    /// Each strongly connected set of mutually recursive nonterminals
+    /// which is neither left-linear nor right-linear is made right-linear
+    /// by cutting each of its rules `A -> α0 B1 α1 ... Bm αm`
+    /// into `A -> α0 B1`, `B1' -> α1 B2`, ..., `Bm' -> αm A'`, with `A' ->` for each member `A`.
+    /// This forgets how many times each nonterminal was entered, so the brackets of `A -> lparen A rparen` no longer have to match.
+    /// Grammars which are already left- or right-linear in this way are converted exactly.
+    ///
+    /// The NFA starts in state 0 and reads the indexes of the grammar's terminals.
+    /// Returns the NFA and its accepting states.
+    pub fn regular_approximation(&self) -> (Nfa, BTreeSet<StateIdx>) {
+        let approximation = self.mohri_nederhof();
+        self.strongly_regular_nfa(&approximation).expect("The approximation is strongly regular")
+    }
+
+    /// Like `regular_approximation`, but determinized and minimized.
+    ///
+    /// The DFA has a column for each symbol of the grammar. The columns of nonterminals lead to a dead state.
+    /// Returns the DFA and its accepting states.
+    pub fn regular_approximation_dfa(&self) -> (Dfa, BTreeSet<StateIdx>) {
+        let (nfa, accepting) = self.regular_approximation();
+        let (dfa, accepting) = nfa.determinize(&accepting);
+        dfa.minimize(&accepting)
+    }
+
+    // A grammar for a superset of the language whose strongly connected sets of nonterminals are all left- or right-linear.
+    // Its terminals have the same names as in the original grammar.
+    fn mohri_nederhof(&self) -> Grammar {
+        let mut draft = Draft::new(self.grammar);
+
+        // A new start symbol, in case the old one is cut up.
+        let start = draft.fresh(&self.grammar.start_symbol().name(), "start");
+        draft.rule(start, vec![self.grammar.start_symbol().name()], Provenance::none());
+
+        for scc in self.dependency_graph().sccs() {
+            if linearity(&scc).is_some() {
+                for rule in scc.iter().flat_map(|symbol| symbol.rules()) {
+                    draft.rule(rule.lhs().name(), rule.rhs().iter().map(|symbol| symbol.name()).collect(), Provenance::none());
+                }
+                continue;
+            }
+
+            let primes: BTreeMap<Symbol<'g>, String> = scc.iter().map(|symbol| (*symbol, draft.fresh(&symbol.name(), "rest"))).collect();
+            for symbol in &scc {
+                for rule in symbol.rules() {
+                    // Cut the RHS after each member of the set.
+                    let mut lhs = symbol.name();
+                    let mut rhs = vec![];
+                    for child in rule.rhs() {
+                        rhs.push(child.name());
+                        if scc.contains(&child) {
+                            draft.rule(lhs, rhs, Provenance::none());
+                            lhs = primes[&child].clone();
+                            rhs = vec![];
+                        }
+                    }
+                    rhs.push(primes[symbol].clone());
+                    draft.rule(lhs, rhs, Provenance::none());
+                }
+                draft.rule(primes[symbol].clone(), vec![], Provenance::none());
+            }
+        }
+
+        draft.grammar()
+    }
+}
@@ -0,0 +1,263 @@
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use super::*;
+
+/// The graph of which nonterminals use which.
+///
+/// There is an edge from `A` to `B` whenever `B` appears on the RHS of a rule for `A`.
+pub struct DependencyGraph<'g> {
+    grammar: &'g Grammar,
+    edges: BTreeMap<Symbol<'g>, BTreeSet<Symbol<'g>>>,
+}
+
+/// A nonterminal which derives itself, with a witness.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recursion<'g> {
+    symbol: Symbol<'g>,
+    kind: RecursionKind,
+    rules: Vec<Rule<'g>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecursionKind {
+    /// `A` derives `A α`.
+    Left,
+
+    /// `A` derives `α A`.
+    Right,
+
+    /// `A` derives `A` on its own.
+    /// A grammar with a cycle is ambiguous, and no parser can handle it.
+    Cyclic,
+}
+
+impl<'g> DependencyGraph<'g> {
+    pub fn grammar(&self) -> &'g Grammar {
+        self.grammar
+    }
+
+    /// The nonterminals which appear on the RHS of the rules for `symbol`.
+    pub fn dependencies(&self, symbol: Symbol<'g>) -> BTreeSet<Symbol<'g>> {
+        self.edges.get(&symbol).cloned().unwrap_or_default()
+    }
+
+    /// The nonterminals with a rule in which `symbol` appears on the RHS.
+    pub fn dependents(&self, symbol: Symbol<'g>) -> BTreeSet<Symbol<'g>> {
+        self.edges
+            .iter()
+            .filter(|(_from, tos)| tos.contains(&symbol))
+            .map(|(from, _tos)| *from)
+            .collect()
+    }
+
+    /// The strongly connected components of the graph.
+    ///
+    /// Each component is a set of nonterminals which all use each other, directly or indirectly.
+    /// Each component comes after every component it depends on.
+    pub fn sccs(&self) -> Vec<BTreeSet<Symbol<'g>>> {
+        let mut tarjan = Tarjan {
+            graph: self,
+            index: BTreeMap::new(),
+            lowlink: BTreeMap::new(),
+            stack: vec![],
+            sccs: vec![],
+        };
+        for symbol in self.grammar.nonterminals() {
+            if !tarjan.index.contains_key(&symbol) {
+                tarjan.visit(symbol);
+            }
+        }
+        tarjan.sccs
+    }
+
+    /// Render the graph in the DOT language used by Graphviz.
+    ///
+    /// The nonterminals of each strongly connected component with more than one member are grouped into a cluster.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph dependencies {\n");
+
+        for (i, scc) in self.sccs().iter().enumerate() {
+            if scc.len() > 1 {
+                dot.push_str(&format!("    subgraph cluster_{i} {{\n"));
+                for symbol in scc {
+                    dot.push_str(&format!("        \"{}\";\n", symbol.name()));
+                }
+                dot.push_str("    }\n");
+            } else {
+                for symbol in scc {
+                    dot.push_str(&format!("    \"{}\";\n", symbol.name()));
+                }
+            }
+        }
+
+        for (from, tos) in &self.edges {
+            for to in tos {
+                dot.push_str(&format!("    \"{}\" -> \"{}\";\n", from.name(), to.name()));
+            }
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+// Tarjan's algorithm for strongly connected components.
+struct Tarjan<'g, 'a> {
+    graph: &'a DependencyGraph<'g>,
+    index: BTreeMap<Symbol<'g>, usize>,
+    lowlink: BTreeMap<Symbol<'g>, usize>,
+    stack: Vec<Symbol<'g>>,
+    sccs: Vec<BTreeSet<Symbol<'g>>>,
+}
+
+impl<'g, 'a> Tarjan<'g, 'a> {
+    fn visit(&mut self, symbol: Symbol<'g>) {
+        let index = self.index.len();
+        self.index.insert(symbol, index);
+        self.lowlink.insert(symbol, index);
+        self.stack.push(symbol);
+
+        for next in self.graph.dependencies(symbol) {
+            if !self.index.contains_key(&next) {
+                self.visit(next);
+                let lowlink = self.lowlink[&symbol].min(self.lowlink[&next]);
+                self.lowlink.insert(symbol, lowlink);
+            } else if self.stack.contains(&next) {
+                let lowlink = self.lowlink[&symbol].min(self.index[&next]);
+                self.lowlink.insert(symbol, lowlink);
+            }
+        }
+
+        if self.lowlink[&symbol] == self.index[&symbol] {
+            let mut scc = BTreeSet::new();
+            loop {
+                let member = self.stack.pop().unwrap();
+                scc.insert(member);
+                if member == symbol {
+                    break;
+                }
+            }
+            self.sccs.push(scc);
+        }
+    }
+}
+
+impl<'g> Recursion<'g> {
+    /// The recursive nonterminal.
+    pub fn symbol(&self) -> Symbol<'g> {
+        self.symbol
+    }
+
+    pub fn kind(&self) -> RecursionKind {
+        self.kind
+    }
+
+    /// A shortest chain of rules by which the nonterminal derives itself.
+    ///
+    /// The first rule has the nonterminal on its LHS,
+    /// the LHS of each following rule appears on the RHS of the one before,
+    /// and the nonterminal appears on the RHS of the last rule.
+    pub fn rules(&self) -> &[Rule<'g>] {
+        &self.rules
+    }
+
+    /// Is the recursion through a single rule?
+    pub fn is_direct(&self) -> bool {
+        self.rules.len() == 1
+    }
+}
+
+impl<'g> GrammarAnalysis<'g> {
+    /// The graph of which nonterminals use which.
+    pub fn dependency_graph(&self) -> DependencyGraph<'g> {
+        let mut edges: BTreeMap<Symbol<'g>, BTreeSet<Symbol<'g>>> = BTreeMap::new();
+        for symbol in self.grammar.nonterminals() {
+            edges.insert(symbol, BTreeSet::new());
+        }
+        for rule in self.grammar.rules() {
+            for symbol in rule.rhs() {
+                if symbol.is_nonterminal() {
+                    edges.get_mut(&rule.lhs()).unwrap().insert(symbol);
+                }
+            }
+        }
+
+        DependencyGraph {
+            grammar: self.grammar,
+            edges,
+        }
+    }
+
+    /// Find the left-recursive nonterminals.
+    ///
+    /// This includes left recursion hidden behind nullable symbols.
+    pub fn left_recursion(&self) -> Vec<Recursion<'g>> {
+        self.recursion(RecursionKind::Left)
+    }
+
+    /// Find the right-recursive nonterminals.
+    ///
+    /// This includes right recursion hidden behind nullable symbols.
+    pub fn right_recursion(&self) -> Vec<Recursion<'g>> {
+        self.recursion(RecursionKind::Right)
+    }
+
+    /// Find the nonterminals which can derive themselves on their own.
+    pub fn cycles(&self) -> Vec<Recursion<'g>> {
+        self.recursion(RecursionKind::Cyclic)
+    }
+
+    fn recursion(&self, kind: RecursionKind) -> Vec<Recursion<'g>> {
+        // An edge for each occurrence of a nonterminal which has only nullable symbols on the relevant sides.
+        let mut edges: BTreeMap<Symbol<'g>, Vec<(Rule<'g>, Symbol<'g>)>> = BTreeMap::new();
+        for rule in self.grammar.rules() {
+            let rhs = rule.rhs();
+            for (i, symbol) in rhs.iter().enumerate() {
+                let before = self.is_nullable_seq(&rhs[..i]);
+                let after = self.is_nullable_seq(&rhs[i + 1..]);
+                let included = match kind {
+                    RecursionKind::Left => before,
+                    RecursionKind::Right => after,
+                    RecursionKind::Cyclic => before && after,
+                };
+                if symbol.is_nonterminal() && included {
+                    edges.entry(rule.lhs()).or_default().push((rule, *symbol));
+                }
+            }
+        }
+
+        let mut result = vec![];
+        for symbol in self.grammar.nonterminals() {
+            if let Some(rules) = shortest_cycle(&edges, symbol) {
+                result.push(Recursion { symbol, kind, rules });
+            }
+        }
+        result
+    }
+}
+
+// Breadth-first search for the shortest path of edges from `symbol` back to itself.
+fn shortest_cycle<'g>(edges: &BTreeMap<Symbol<'g>, Vec<(Rule<'g>, Symbol<'g>)>>, symbol: Symbol<'g>) -> Option<Vec<Rule<'g>>> {
+    let mut parents: BTreeMap<Symbol<'g>, (Rule<'g>, Symbol<'g>)> = BTreeMap::new();
+    let mut queue = VecDeque::from([symbol]);
+
+    while let Some(current) = queue.pop_front() {
+        for (rule, next) in edges.get(&current).into_iter().flatten() {
+            if *next == symbol {
+                let mut rules = vec![*rule];
+                let mut at = current;
+                while at != symbol {
+                    let (rule, parent) = parents[&at];
+                    rules.insert(0, rule);
+                    at = parent;
+                }
+                return Some(rules);
+            }
+            if !parents.contains_key(next) {
+                parents.insert(*next, (*rule, current));
+                queue.push_back(*next);
+            }
+        }
+    }
+    None
+}
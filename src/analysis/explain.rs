@@ -0,0 +1,95 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use super::*;
+
+impl<'g> GrammarAnalysis<'g> {
+    /// Explain why `symbol` is nullable.
+    ///
+    /// The result is a leftmost derivation of `symbol` into ε,
+    /// or `None` if `symbol` isn't nullable.
+    pub fn explain_nullable(&self, symbol: Symbol<'g>) -> Option<Vec<Rule<'g>>> {
+        if !self.is_nullable(symbol) {
+            return None;
+        }
+
+        // Remember the rule which first made each symbol nullable.
+        // Following these rules always terminates.
+        let mut nullable_rules: BTreeMap<Symbol<'g>, Rule<'g>> = BTreeMap::new();
+        while !nullable_rules.contains_key(&symbol) {
+            for rule in self.grammar.rules() {
+                if !nullable_rules.contains_key(&rule.lhs()) &&
+                    rule.rhs().iter().all(|symbol| nullable_rules.contains_key(symbol)) {
+                    nullable_rules.insert(rule.lhs(), rule);
+                }
+            }
+        }
+
+        let mut derivation = vec![];
+        let mut stack = vec![symbol];
+        while let Some(symbol) = stack.pop() {
+            let rule = nullable_rules[&symbol];
+            derivation.push(rule);
+            stack.extend(rule.rhs().into_iter().rev());
+        }
+        Some(derivation)
+    }
+
+    /// Explain why `terminal` is in the FIRST set of `symbol`.
+    ///
+    /// The result is a shortest leftmost derivation of `symbol`
+    /// into a sentential form starting with `terminal`,
+    /// or `None` if `terminal` isn't in the FIRST set.
+    pub fn explain_first(&self, symbol: Symbol<'g>, terminal: Symbol<'g>) -> Option<Vec<Rule<'g>>> {
+        self.explain(FFNode::First(symbol), terminal)
+    }
+
+    /// Explain why `terminal` is in the FOLLOW set of `symbol`.
+    ///
+    /// The result is a chain of rules, innermost first.
+    /// The first rule has `symbol` on its RHS.
+    /// Each rule is followed by the derivations into ε of the symbols after `symbol` (or the LHS of the previous rule),
+    /// up to either the symbol which derives `terminal` or the end of the RHS.
+    /// In the first case, the chain ends with a derivation of that symbol into a sentential form starting with `terminal`.
+    /// In the second case, the chain continues with a rule which has the LHS on its RHS.
+    ///
+    /// Returns `None` if `terminal` isn't in the FOLLOW set.
+    pub fn explain_follow(&self, symbol: Symbol<'g>, terminal: Symbol<'g>) -> Option<Vec<Rule<'g>>> {
+        self.explain(FFNode::Follow(symbol), terminal)
+    }
+
+    // Find a shortest path from `from_node` to `terminal` in the `FirstFollows` graph,
+    // and give the reason for each edge along the way.
+    fn explain(&self, from_node: FFNode<'g>, terminal: Symbol<'g>) -> Option<Vec<Rule<'g>>> {
+        let to_node = FFNode::Terminal(terminal);
+        let mut parents: BTreeMap<FFNode<'g>, FFNode<'g>> = BTreeMap::new();
+        let mut queue = VecDeque::from([from_node]);
+
+        while let Some(node) = queue.pop_front() {
+            if node == to_node {
+                break;
+            }
+            for next_node in self.first_follows.edges.get(&node).into_iter().flat_map(|edges| edges.keys()) {
+                if *next_node != from_node && !parents.contains_key(next_node) {
+                    parents.insert(*next_node, node);
+                    queue.push_back(*next_node);
+                }
+            }
+        }
+
+        let mut path = vec![to_node];
+        while *path.last().unwrap() != from_node {
+            path.push(*parents.get(path.last().unwrap())?);
+        }
+        path.reverse();
+
+        let mut derivation = vec![];
+        for edge in path.windows(2) {
+            let reason = &self.first_follows.edges[&edge[0]][&edge[1]];
+            derivation.push(reason.rule);
+            for symbol in &reason.rule.rhs()[reason.nullable.clone()] {
+                derivation.extend(self.explain_nullable(*symbol).unwrap());
+            }
+        }
+        Some(derivation)
+    }
+}
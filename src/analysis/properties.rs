@@ -0,0 +1,286 @@
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use super::*;
+use crate::nfa::{Nfa, StateIdx};
+
+impl<'g> GrammarAnalysis<'g> {
+    /// Is the language of the grammar empty?
+    ///
+    /// This is the case when the start symbol can't derive any string of terminals.
+    pub fn is_empty(&self) -> bool {
+        !self.productives.contains(&self.grammar.start_symbol())
+    }
+
+    /// Is the language of the grammar finite?
+    ///
+    /// It is infinite exactly when some useful nonterminal `A` derives `α A β`
+    /// where `α β` derives a nonempty string of terminals.
+    pub fn is_finite(&self) -> bool {
+        self.useful_nonterminals()
+            .into_iter()
+            .all(|symbol| self.self_derivations(symbol).iter().all(|(left, right)| !left && !right))
+    }
+
+    /// Every sentence of the language, in shortlex order, if the language is finite.
+    pub fn finite_language(&self) -> Option<Vec<Vec<Symbol<'g>>>> {
+        if !self.is_finite() {
+            return None;
+        }
+        if self.is_empty() {
+            return Some(vec![]);
+        }
+
+        // Without a cycle which adds terminals, the longest yields settle.
+        let mut longest: BTreeMap<Symbol<'g>, usize> = self.grammar.terminals().into_iter().map(|symbol| (symbol, 1)).collect();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for rule in self.grammar.rules() {
+                let length: Option<usize> = rule.rhs().iter().map(|symbol| longest.get(symbol).copied()).sum();
+                if let Some(length) = length && longest.get(&rule.lhs()).is_none_or(|old| length > *old) {
+                    longest.insert(rule.lhs(), length);
+                    changed = true;
+                }
+            }
+        }
+
+        Some(generate::enumerate(self.grammar, longest[&self.grammar.start_symbol()]))
+    }
+
+    /// Is the grammar self-embedding?
+    ///
+    /// A grammar is self-embedding when some useful nonterminal `A` derives `α A β`
+    /// where both `α` and `β` derive nonempty strings of terminals.
+    /// A grammar which is not self-embedding generates a regular language (see `to_nfa`).
+    /// The converse doesn't hold: a self-embedding grammar may still generate a regular language.
+    pub fn is_self_embedding(&self) -> bool {
+        self.useful_nonterminals()
+            .into_iter()
+            .any(|symbol| self.self_derivations(symbol).contains(&(true, true)))
+    }
+
+    /// Convert a grammar which is not self-embedding into an equivalent NFA.
+    ///
+    /// The NFA starts in state 0, reads the indexes of the grammar's terminals,
+    /// and accepts the sentences of the grammar in the states of the returned set.
+    /// Returns `None` if the grammar is self-embedding.
+    ///
+    /// The construction is Nederhof's: after removing ε-rules and useless symbols,
+    /// each strongly connected set of mutually recursive nonterminals is either left-linear or right-linear,
+    /// and becomes a set of states of the NFA.
+    pub fn to_nfa(&self) -> Option<(Nfa, BTreeSet<StateIdx>)> {
+        if self.is_self_embedding() {
+            return None;
+        }
+
+        if self.is_empty() {
+            return Some((Nfa::new(1, self.grammar.symbols().len() as u32), BTreeSet::new()));
+        }
+
+        let transformed = self.grammar.without_epsilon_rules().then(Grammar::reduced);
+        self.strongly_regular_nfa(transformed.grammar())
+    }
+
+    // Nederhof's construction of an NFA for a grammar for the same terminals,
+    // whose strongly connected sets of nonterminals are all left-linear or right-linear.
+    // Returns `None` if one isn't.
+    pub(super) fn strongly_regular_nfa(&self, grammar: &Grammar) -> Option<(Nfa, BTreeSet<StateIdx>)> {
+        let analysis = GrammarAnalysis::build(grammar);
+        let mut builder = NfaBuilder {
+            original: self.grammar,
+            sccs: vec![],
+            membership: BTreeMap::new(),
+            num_states: 2,
+            transitions: vec![],
+        };
+        for scc in analysis.dependency_graph().sccs() {
+            let linearity = linearity(&scc)?;
+            for symbol in &scc {
+                builder.membership.insert(*symbol, builder.sccs.len());
+            }
+            builder.sccs.push((scc, linearity));
+        }
+        builder.symbol(0, grammar.start_symbol(), 1);
+
+        let mut nfa = Nfa::new(builder.num_states as u32, self.grammar.symbols().len() as u32);
+        for (from, to, symbol) in builder.transitions {
+            match symbol {
+                Some(symbol) => nfa.add_transition(from, to, symbol),
+                None => nfa.add_free_transition(from, to),
+            }
+        }
+        Some((nfa, BTreeSet::from([1])))
+    }
+
+    fn useful_nonterminals(&self) -> Vec<Symbol<'g>> {
+        self.grammar
+            .nonterminals()
+            .into_iter()
+            .filter(|symbol| self.productives.contains(symbol) && self.reachables.contains(symbol))
+            .collect()
+    }
+
+    // The ways `symbol` can derive a sentential form containing itself, through useful rules.
+    // Each is whether anything on the left, and on the right, of the symbol derives a nonempty string of terminals.
+    fn self_derivations(&self, symbol: Symbol<'g>) -> BTreeSet<(bool, bool)> {
+        let nonempty = self.nonempty_symbols();
+        let is_useful = |rule: &Rule<'g>| rule.rhs().iter().all(|symbol| self.productives.contains(symbol));
+
+        let mut visited = BTreeSet::new();
+        let mut result = BTreeSet::new();
+        let mut queue = VecDeque::from([(symbol, false, false)]);
+        while let Some((current, left, right)) = queue.pop_front() {
+            for rule in current.rules().iter().filter(|rule| is_useful(rule)) {
+                let rhs = rule.rhs();
+                for (i, child) in rhs.iter().enumerate() {
+                    if child.is_terminal() {
+                        continue;
+                    }
+                    let node = (
+                        *child,
+                        left || rhs[..i].iter().any(|symbol| nonempty.contains(symbol)),
+                        right || rhs[i + 1..].iter().any(|symbol| nonempty.contains(symbol)),
+                    );
+                    if *child == symbol {
+                        result.insert((node.1, node.2));
+                    }
+                    if visited.insert(node) {
+                        queue.push_back(node);
+                    }
+                }
+            }
+        }
+        result
+    }
+
+    // The symbols which derive at least one nonempty string of terminals.
+    fn nonempty_symbols(&self) -> BTreeSet<Symbol<'g>> {
+        let mut nonempty: BTreeSet<Symbol<'g>> = self.grammar.terminals().into_iter().collect();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for rule in self.grammar.rules() {
+                let rhs = rule.rhs();
+                if !nonempty.contains(&rule.lhs()) &&
+                    rhs.iter().all(|symbol| self.productives.contains(symbol)) &&
+                    rhs.iter().any(|symbol| nonempty.contains(symbol)) {
+                    nonempty.insert(rule.lhs());
+                    changed = true;
+                }
+            }
+        }
+        nonempty
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(super) enum Linearity {
+    // The nonterminal isn't recursive.
+    None,
+    // Members of the set only appear at the start of the rules for members.
+    Left,
+    // Members of the set only appear at the end of the rules for members.
+    Right,
+}
+
+// Classify a strongly connected set of nonterminals, or return `None` if it is neither left- nor right-linear.
+pub(super) fn linearity<'g>(scc: &BTreeSet<Symbol<'g>>) -> Option<Linearity> {
+    let mut recursive = false;
+    let mut left = true;
+    let mut right = true;
+    for rule in scc.iter().flat_map(|symbol| symbol.rules()) {
+        let rhs = rule.rhs();
+        for (i, symbol) in rhs.iter().enumerate() {
+            if scc.contains(symbol) {
+                recursive = true;
+                left &= i == 0;
+                right &= i + 1 == rhs.len();
+            }
+        }
+    }
+
+    if !recursive && scc.len() == 1 {
+        Some(Linearity::None)
+    } else if left {
+        Some(Linearity::Left)
+    } else if right {
+        Some(Linearity::Right)
+    } else {
+        None
+    }
+}
+
+struct NfaBuilder<'g, 't> {
+    original: &'g Grammar,
+    // The strongly connected sets of nonterminals, and which one each nonterminal is in.
+    sccs: Vec<(BTreeSet<Symbol<'t>>, Linearity)>,
+    membership: BTreeMap<Symbol<'t>, usize>,
+    num_states: usize,
+    transitions: Vec<(StateIdx, StateIdx, Option<usize>)>,
+}
+
+impl<'g, 't> NfaBuilder<'g, 't> {
+    fn fresh(&mut self) -> StateIdx {
+        self.num_states += 1;
+        self.num_states - 1
+    }
+
+    // Add transitions from `from` to `to` which read the strings `symbols` derives.
+    fn sequence(&mut self, from: StateIdx, symbols: &[Symbol<'t>], to: StateIdx) {
+        match symbols {
+            [] => self.transitions.push((from, to, None)),
+            [symbol] => self.symbol(from, *symbol, to),
+            [symbol, rest @ ..] => {
+                let middle = self.fresh();
+                self.symbol(from, *symbol, middle);
+                self.sequence(middle, rest, to);
+            }
+        }
+    }
+
+    // Add transitions from `from` to `to` which read the strings `symbol` derives.
+    fn symbol(&mut self, from: StateIdx, symbol: Symbol<'t>, to: StateIdx) {
+        if symbol.is_terminal() {
+            let terminal = self.original.symbol(symbol.name()).unwrap();
+            self.transitions.push((from, to, Some(usize::from(terminal.index()))));
+            return;
+        }
+
+        let (scc, linearity) = self.sccs[self.membership[&symbol]].clone();
+        match linearity {
+            Linearity::None => {
+                for rule in symbol.rules() {
+                    self.sequence(from, &rule.rhs(), to);
+                }
+            }
+            // A state for each member, reached after reading a string it derives.
+            Linearity::Left => {
+                let states: BTreeMap<Symbol<'t>, StateIdx> = scc.iter().map(|member| (*member, self.fresh())).collect();
+                for member in scc.iter() {
+                    for rule in member.rules() {
+                        let rhs = rule.rhs();
+                        match rhs.first() {
+                            Some(first) if scc.contains(first) => self.sequence(states[first], &rhs[1..], states[member]),
+                            _ => self.sequence(from, &rhs, states[member]),
+                        }
+                    }
+                }
+                self.transitions.push((states[&symbol], to, None));
+            }
+            // A state for each member, from which the rest of a string it derives is read.
+            Linearity::Right => {
+                let states: BTreeMap<Symbol<'t>, StateIdx> = scc.iter().map(|member| (*member, self.fresh())).collect();
+                for member in scc.iter() {
+                    for rule in member.rules() {
+                        let rhs = rule.rhs();
+                        match rhs.split_last() {
+                            Some((last, init)) if scc.contains(last) => self.sequence(states[member], init, states[last]),
+                            _ => self.sequence(states[member], &rhs, to),
+                        }
+                    }
+                }
+                self.transitions.push((from, states[&symbol], None));
+            }
+        }
+    }
+}
@@ -1,3 +1,5 @@
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
 #[derive(Debug)]
 pub struct Dfa {
     transitions: Vec<Vec<StateIdx>>,
@@ -30,6 +32,77 @@ impl Dfa {
         self.state
     }
 
+    pub fn num_states(&self) -> usize {
+        self.transitions.len()
+    }
+
+    pub fn num_symbols(&self) -> usize {
+        self.transitions[0].len()
+    }
+
+    /// The state reached from `state` on `symbol`, without moving the machine.
+    pub fn transition(&self, state: StateIdx, symbol: SymbolIdx) -> StateIdx {
+        self.transitions[state][symbol]
+    }
+
+    /// Build the minimal DFA for the same language, given the accepting states.
+    ///
+    /// Unreachable states are dropped, and the remaining ones are merged
+    /// by refining the partition into accepting and other states until it is stable.
+    /// Returns the DFA and its accepting states.
+    pub fn minimize(&self, accepting: &BTreeSet<StateIdx>) -> (Dfa, BTreeSet<StateIdx>) {
+        // The reachable states, in breadth-first order from the initial state.
+        let mut reachable = vec![0];
+        let mut visited = BTreeSet::from([0]);
+        let mut queue = VecDeque::from([0]);
+        while let Some(state) = queue.pop_front() {
+            for next in &self.transitions[state] {
+                if visited.insert(*next) {
+                    reachable.push(*next);
+                    queue.push_back(*next);
+                }
+            }
+        }
+
+        let mut classes: BTreeMap<StateIdx, usize> = reachable
+            .iter()
+            .map(|state| (*state, usize::from(accepting.contains(state))))
+            .collect();
+        let mut num_classes = 0;
+        loop {
+            // Number the classes in the order their first states were reached,
+            // so that the initial state is always in class 0.
+            let mut signatures = BTreeMap::new();
+            let mut next_classes = BTreeMap::new();
+            for state in &reachable {
+                let signature: (usize, Vec<usize>) = (
+                    classes[state],
+                    self.transitions[*state].iter().map(|next| classes[next]).collect(),
+                );
+                let count = signatures.len();
+                let class = *signatures.entry(signature).or_insert(count);
+                next_classes.insert(*state, class);
+            }
+
+            classes = next_classes;
+            if signatures.len() == num_classes {
+                break;
+            }
+            num_classes = signatures.len();
+        }
+
+        let mut rows = vec![vec![]; num_classes];
+        let mut class_accepting = BTreeSet::new();
+        for state in &reachable {
+            let class = classes[state];
+            rows[class] = self.transitions[*state].iter().map(|next| classes[next]).collect();
+            if accepting.contains(state) {
+                class_accepting.insert(class);
+            }
+        }
+        (Dfa::new(rows), class_accepting)
+    }
+
     pub fn step(&mut self, symbol: SymbolIdx) -> StateIdx {
         let state_transitions = &self.transitions[self.state];
         let next_state = state_transitions[symbol];
@@ -62,3 +135,22 @@ fn test1() {
     dfa.step(0);
     assert_eq!(dfa.state(), 4);
 }
+
+#[test]
+fn test_minimize() {
+    // Strings over {0, 1} ending in 1, with a redundant copy of each state.
+    let dfa = Dfa::new(vec![
+        vec![2, 1],
+        vec![0, 3],
+        vec![0, 3],
+        vec![2, 1],
+        vec![4, 4],
+    ]);
+    let accepting = [1, 3].into_iter().collect();
+
+    let (minimal, minimal_accepting) = dfa.minimize(&accepting);
+    assert_eq!(minimal.num_states(), 2);
+    assert_eq!(minimal_accepting, [1].into_iter().collect());
+    assert_eq!(minimal.transition(0, 1), 1);
+    assert_eq!(minimal.transition(1, 0), 0);
+}
@@ -0,0 +1,201 @@
+mod coverage;
+mod enumerate;
+mod near_miss;
+
+use std::collections::BTreeMap;
+
+use crate::*;
+
+pub use coverage::{rule_coverage, transition_coverage};
+pub use enumerate::{enumerate, count_sentences, count_derivations, first_ambiguous_length};
+pub use near_miss::{near_misses, Mutation, NearMiss};
+
+/// A small, seedable pseudorandom number generator (SplitMix64).
+///
+/// The same seed always gives the same sequence of numbers, on every platform.
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        Rng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    /// A number in the range `0..bound`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `bound` is zero.
+    pub fn below(&mut self, bound: u64) -> u64 {
+        assert!(bound > 0, "Bound must be positive");
+        // Reject the values which would bias the result towards small numbers.
+        let zone = u64::MAX - u64::MAX % bound;
+        loop {
+            let value = self.next_u64();
+            if value < zone {
+                return value % bound;
+            }
+        }
+    }
+}
+
+/// Generates random sentences of a `Grammar`.
+///
+/// Each nonterminal is expanded by a rule chosen at random, in proportion to the rule's weight.
+/// Once the depth limit is reached, or when a rule would make the sentence longer than the length limit,
+/// the generator falls back on the rule which gives the shortest yield.
+/// Rules which can't derive any string of terminals are never chosen.
+pub struct Generator<'g> {
+    grammar: &'g Grammar,
+    analysis: GrammarAnalysis<'g>,
+    rng: Rng,
+    max_depth: usize,
+    max_length: usize,
+    weights: BTreeMap<Rule<'g>, u32>,
+    // The length of the shortest yield of each productive symbol.
+    min_lengths: BTreeMap<Symbol<'g>, usize>,
+    // The length of the sentence so far, plus the shortest yields of the symbols still to be expanded.
+    committed: usize,
+}
+
+impl<'g> Generator<'g> {
+    pub fn new(grammar: &'g Grammar) -> Generator<'g> {
+        let analysis = GrammarAnalysis::build(grammar);
+        let unproductive = analysis.unproductive();
+        let min_lengths = grammar
+            .symbols()
+            .into_iter()
+            .filter(|symbol| !unproductive.contains(symbol))
+            .map(|symbol| (symbol, analysis.shortest_yield(symbol).len()))
+            .collect();
+
+        Generator {
+            grammar,
+            analysis,
+            rng: Rng::new(0),
+            max_depth: 32,
+            max_length: 256,
+            weights: BTreeMap::new(),
+            min_lengths,
+            committed: 0,
+        }
+    }
+
+    /// Seed the random number generator. The default seed is 0.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.rng = Rng::new(seed);
+        self
+    }
+
+    /// Limit the depth of the parse tree before the generator falls back on the shortest yields.
+    /// The default is 32.
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Limit the length of the sentences.
+    /// The default is 256.
+    ///
+    /// A symbol whose shortest yield is longer than the limit still generates its shortest yield.
+    pub fn max_length(mut self, max_length: usize) -> Self {
+        self.max_length = max_length;
+        self
+    }
+
+    /// Set the weight of a rule. The default weight is 1.
+    ///
+    /// A rule with weight 0 is only chosen when it is the fallback.
+    pub fn weight(mut self, rule: Rule<'g>, weight: u32) -> Self {
+        assert!(std::ptr::eq(rule.grammar(), self.grammar), "Rule belongs to a different grammar: {rule:?}");
+        self.weights.insert(rule, weight);
+        self
+    }
+
+    pub fn grammar(&self) -> &'g Grammar {
+        self.grammar
+    }
+
+    /// Generate a sentence from the start symbol.
+    pub fn generate(&mut self) -> Vec<Symbol<'g>> {
+        self.generate_from(self.grammar.start_symbol())
+    }
+
+    /// Generate a string of terminals from `symbol`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `symbol` is unproductive.
+    pub fn generate_from(&mut self, symbol: Symbol<'g>) -> Vec<Symbol<'g>> {
+        self.generate_tree_from(symbol).leaves()
+    }
+
+    /// Generate a parse tree from `symbol`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `symbol` is unproductive.
+    pub fn generate_tree_from(&mut self, symbol: Symbol<'g>) -> ParseTree<'g> {
+        match self.min_lengths.get(&symbol) {
+            Some(min_length) => self.committed = *min_length,
+            None => panic!("Symbol is unproductive: {symbol:?}"),
+        }
+        self.expand(symbol, 0)
+    }
+
+    fn expand(&mut self, symbol: Symbol<'g>, depth: usize) -> ParseTree<'g> {
+        if symbol.is_terminal() {
+            return ParseTree::Token(symbol);
+        }
+
+        let rule = self.choose(symbol, depth);
+        let rhs_length: usize = rule.rhs().iter().map(|symbol| self.min_lengths[symbol]).sum();
+        self.committed = self.committed + rhs_length - self.min_lengths[&symbol];
+
+        let children = rule.rhs().into_iter().map(|child| self.expand(child, depth + 1)).collect();
+        ParseTree::Node(rule, children)
+    }
+
+    fn choose(&mut self, symbol: Symbol<'g>, depth: usize) -> Rule<'g> {
+        let fallback = self.analysis.shortest_rule(symbol);
+        if depth >= self.max_depth {
+            return fallback;
+        }
+
+        let mut candidates = vec![];
+        let mut total = 0;
+        for rule in symbol.rules() {
+            let rhs_length: Option<usize> = rule.rhs().iter().map(|symbol| self.min_lengths.get(symbol).copied()).sum();
+            let weight = self.weights.get(&rule).copied().unwrap_or(1);
+            if let Some(rhs_length) = rhs_length &&
+                weight > 0 &&
+                self.committed + rhs_length - self.min_lengths[&symbol] <= self.max_length {
+                candidates.push((rule, weight));
+                total += u64::from(weight);
+            }
+        }
+
+        if candidates.is_empty() {
+            return fallback;
+        }
+
+        let mut choice = self.rng.below(total);
+        for (rule, weight) in candidates {
+            if choice < u64::from(weight) {
+                return rule;
+            }
+            choice -= u64::from(weight);
+        }
+        unreachable!()
+    }
+}
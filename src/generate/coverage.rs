@@ -0,0 +1,302 @@
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::collections::btree_map::Entry;
+
+use crate::*;
+use crate::lr1::{Action, ParseTable, StateIndex};
+
+/// Generate a small set of parse trees which together use every rule of `grammar` at least once,
+/// following Purdom's algorithm.
+///
+/// Each tree is built for a rule which no earlier tree uses.
+/// The tree reaches the rule's LHS from the start symbol along the shortest possible context,
+/// and every other nonterminal is expanded by a rule which hasn't been used yet when there is one,
+/// or else by its shortest derivation.
+///
+/// Rules which can't appear in any parse tree for a sentence are not covered.
+/// Use `ParseTree::leaves` to get the sentences.
+pub fn rule_coverage(grammar: &Grammar) -> Vec<ParseTree<'_>> {
+    let mut coverage = RuleCoverage::new(grammar);
+    let mut trees = vec![];
+    for rule in grammar.rules() {
+        if coverage.used.contains(&rule) || !coverage.is_coverable(rule) {
+            continue;
+        }
+        trees.push(coverage.tree_using(rule));
+    }
+    trees
+}
+
+/// Generate a small set of parse trees for the grammar of `table`
+/// which together take every transition (every `Shift` action, including GOTOs) of the table.
+///
+/// Each tree is built for a transition which no earlier tree takes.
+/// The tree follows a shortest sequence of transitions to the transition's state,
+/// and completes the items there with shortest derivations.
+///
+/// Transitions which no parse can take, such as those on unproductive symbols, are not covered.
+/// The table should be free of conflicts for the trees to be the ones the parser builds.
+pub fn transition_coverage<'g>(table: &ParseTable<'g>) -> Vec<ParseTree<'g>> {
+    let grammar = table.grammar();
+    let analysis = GrammarAnalysis::build(grammar);
+    let unproductive = analysis.unproductive();
+    let paths = access_paths(table, &unproductive);
+
+    let mut taken = BTreeSet::new();
+    let mut trees = vec![];
+    for (i, _state) in table.states().iter().enumerate() {
+        let state = StateIndex(i);
+        let Some(path) = paths.get(&state) else {
+            continue;
+        };
+
+        for symbol in grammar.symbols() {
+            if taken.contains(&(state, symbol)) || goto(table, state, symbol).is_none() {
+                continue;
+            }
+            if let Some(tree) = tree_taking(table, &analysis, &unproductive, path, symbol) {
+                transitions(table, &tree, &mut vec![], &mut taken);
+                trees.push(tree);
+            }
+        }
+    }
+    trees
+}
+
+struct RuleCoverage<'g> {
+    grammar: &'g Grammar,
+    analysis: GrammarAnalysis<'g>,
+    unproductive: BTreeSet<Symbol<'g>>,
+    // The length of the shortest yield of each productive symbol.
+    min_lengths: BTreeMap<Symbol<'g>, usize>,
+    // For each reachable nonterminal, the rule and position through which it is reached
+    // in the shortest sentence which uses it.
+    contexts: BTreeMap<Symbol<'g>, (Rule<'g>, usize)>,
+    used: BTreeSet<Rule<'g>>,
+}
+
+impl<'g> RuleCoverage<'g> {
+    fn new(grammar: &'g Grammar) -> RuleCoverage<'g> {
+        let analysis = GrammarAnalysis::build(grammar);
+        let unproductive = analysis.unproductive();
+        let min_lengths = grammar
+            .symbols()
+            .into_iter()
+            .filter(|symbol| !unproductive.contains(symbol))
+            .map(|symbol| (symbol, analysis.shortest_yield(symbol).len()))
+            .collect();
+        let contexts = analysis.shortest_contexts();
+
+        RuleCoverage {
+            grammar,
+            analysis,
+            unproductive,
+            min_lengths,
+            contexts,
+            used: BTreeSet::new(),
+        }
+    }
+
+    fn is_productive(&self, rule: Rule<'g>) -> bool {
+        rule.rhs().iter().all(|symbol| !self.unproductive.contains(symbol))
+    }
+
+    // Can `rule` appear in a parse tree for a sentence?
+    fn is_coverable(&self, rule: Rule<'g>) -> bool {
+        let reachable = rule.lhs() == self.grammar.start_symbol() || self.contexts.contains_key(&rule.lhs());
+        reachable && self.is_productive(rule)
+    }
+
+    fn rhs_length(&self, rule: Rule<'g>) -> usize {
+        rule.rhs().iter().map(|symbol| self.min_lengths[symbol]).sum()
+    }
+
+    // Build a parse tree from the start symbol which uses `rule`.
+    fn tree_using(&mut self, rule: Rule<'g>) -> ParseTree<'g> {
+        let start_symbol = self.grammar.start_symbol();
+
+        let mut context = vec![];
+        let mut symbol = rule.lhs();
+        while symbol != start_symbol {
+            let (outer, position) = self.contexts[&symbol];
+            context.push((outer, position));
+            symbol = outer.lhs();
+        }
+
+        self.used.insert(rule);
+        for (outer, _position) in &context {
+            self.used.insert(*outer);
+        }
+
+        let mut tree = self.expand_with(rule, None);
+        for (outer, position) in context {
+            tree = self.expand_with(outer, Some((position, tree)));
+        }
+        tree
+    }
+
+    // Expand the RHS of `rule`, with `inner` as the child at its position.
+    fn expand_with(&mut self, rule: Rule<'g>, inner: Option<(usize, ParseTree<'g>)>) -> ParseTree<'g> {
+        let mut inner = inner;
+        let mut children = vec![];
+        for (position, symbol) in rule.rhs().into_iter().enumerate() {
+            match inner.take_if(|(inner_position, _tree)| *inner_position == position) {
+                Some((_position, tree)) => children.push(tree),
+                None => children.push(self.expand(symbol)),
+            }
+        }
+        ParseTree::Node(rule, children)
+    }
+
+    // Purdom's third phase: prefer the unused rule with the shortest yield.
+    // Each rule is only preferred once, so this always terminates.
+    fn expand(&mut self, symbol: Symbol<'g>) -> ParseTree<'g> {
+        let unused = symbol
+            .rules()
+            .into_iter()
+            .filter(|rule| !self.used.contains(rule) && self.is_coverable(*rule))
+            .min_by_key(|rule| self.rhs_length(*rule));
+
+        match unused {
+            Some(rule) => {
+                self.used.insert(rule);
+                self.expand_with(rule, None)
+            }
+            None => self.analysis.shortest_derivation(symbol),
+        }
+    }
+}
+
+// The state reached from `state` on `symbol`, if there is a transition.
+fn goto<'g>(table: &ParseTable<'g>, state: StateIndex, symbol: Symbol<'g>) -> Option<StateIndex> {
+    table.get(state, Some(symbol)).into_iter().find_map(|action| match action {
+        Action::Shift(state) => Some(state),
+        Action::Reduce(_rule) => None,
+    })
+}
+
+// For each state, the states along a shortest sequence of transitions on productive symbols
+// from the initial state, ending with the state itself.
+fn access_paths<'g>(table: &ParseTable<'g>, unproductive: &BTreeSet<Symbol<'g>>) -> BTreeMap<StateIndex, Vec<StateIndex>> {
+    let mut paths = BTreeMap::from([(StateIndex(0), vec![StateIndex(0)])]);
+    let mut queue = VecDeque::from([StateIndex(0)]);
+    while let Some(state) = queue.pop_front() {
+        for symbol in table.grammar().symbols() {
+            if unproductive.contains(&symbol) {
+                continue;
+            }
+            if let Some(next) = goto(table, state, symbol) && !paths.contains_key(&next) {
+                let mut path = paths[&state].clone();
+                path.push(next);
+                paths.insert(next, path);
+                queue.push_back(next);
+            }
+        }
+    }
+    paths
+}
+
+// Build a parse tree which takes the transition on `symbol` from the last state of `path`.
+//
+// This traces an item with `symbol` after the dot back to the start rule:
+// a kernel item comes from the same rule one step back along the path,
+// and a closure item comes from an item in the same state with its LHS after the dot.
+// The rules met along the way are the spine of the tree.
+fn tree_taking<'g>(
+    table: &ParseTable<'g>,
+    analysis: &GrammarAnalysis<'g>,
+    unproductive: &BTreeSet<Symbol<'g>>,
+    path: &[StateIndex],
+    symbol: Symbol<'g>,
+) -> Option<ParseTree<'g>> {
+    let grammar = table.grammar();
+    let is_productive = |rule: Rule<'g>| rule.rhs().iter().all(|symbol| !unproductive.contains(symbol));
+
+    let last = path.len() - 1;
+    let target = table[path[last]]
+        .items()
+        .iter()
+        .find(|item| item.next_symbol() == Some(symbol) && is_productive(item.rule()))?;
+
+    // Search backwards from the target item for the start item.
+    // Each node is an item (as a rule and a position) in the state at an index along the path.
+    let target = (last, target.rule(), target.pos());
+    let goal = (0, grammar.start_rule(), 0);
+    let mut successors = BTreeMap::from([(target, target)]);
+    let mut queue = VecDeque::from([target]);
+    while let Some(node) = queue.pop_front() {
+        if node == goal {
+            break;
+        }
+
+        let (i, rule, pos) = node;
+        let mut predecessors = vec![];
+        if pos > 0 {
+            predecessors.push((i - 1, rule, pos - 1));
+        } else {
+            for item in table[path[i]].items() {
+                if item.next_symbol() == Some(rule.lhs()) && is_productive(item.rule()) {
+                    predecessors.push((i, item.rule(), item.pos()));
+                }
+            }
+        }
+
+        for predecessor in predecessors {
+            if let Entry::Vacant(entry) = successors.entry(predecessor) {
+                entry.insert(node);
+                queue.push_back(predecessor);
+            }
+        }
+    }
+
+    // Walk forwards from the start item, keeping the position in each rule where the next one begins.
+    successors.get(&goal)?;
+    let mut node = goal;
+    let mut spine = vec![];
+    while node != target {
+        let next = successors[&node];
+        if next.1 != node.1 || next.2 != node.2 + 1 {
+            spine.push((node.1, node.2));
+        }
+        node = next;
+    }
+
+    let mut tree = ParseTree::Node(target.1, target.1.rhs().into_iter().map(|symbol| analysis.shortest_derivation(symbol)).collect());
+    for (rule, position) in spine.into_iter().rev() {
+        let children = rule
+            .rhs()
+            .into_iter()
+            .enumerate()
+            .map(|(i, symbol)| if i == position {
+                tree.clone()
+            } else {
+                analysis.shortest_derivation(symbol)
+            })
+            .collect();
+        tree = ParseTree::Node(rule, children);
+    }
+    Some(tree)
+}
+
+// Record the transitions taken while parsing `tree`, starting from the states on `stack`.
+fn transitions<'g>(
+    table: &ParseTable<'g>,
+    tree: &ParseTree<'g>,
+    stack: &mut Vec<StateIndex>,
+    taken: &mut BTreeSet<(StateIndex, Symbol<'g>)>,
+) {
+    if let ParseTree::Node(rule, children) = tree {
+        for child in children {
+            transitions(table, child, stack, taken);
+        }
+        stack.truncate(stack.len() - children.len());
+        if *rule == table.grammar().start_rule() {
+            return;
+        }
+    }
+
+    let state = stack.last().copied().unwrap_or(StateIndex(0));
+    let symbol = tree.symbol();
+    let next = goto(table, state, symbol).unwrap_or_else(|| panic!("No transition on {symbol:?} from {state:?}"));
+    taken.insert((state, symbol));
+    stack.push(next);
+}
@@ -0,0 +1,155 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::*;
+
+/// Every sentence of `grammar` with at most `max_length` terminals, in shortlex order.
+///
+/// Shorter sentences come first, and sentences of the same length are ordered
+/// by comparing their symbols from left to right.
+pub fn enumerate(grammar: &Grammar, max_length: usize) -> Vec<Vec<Symbol<'_>>> {
+    let mut yields: BTreeMap<Symbol<'_>, Vec<BTreeSet<Vec<Symbol<'_>>>>> = BTreeMap::new();
+    for symbol in grammar.symbols() {
+        let mut by_length = vec![BTreeSet::new(); max_length + 1];
+        if symbol.is_terminal() && max_length > 0 {
+            by_length[1].insert(vec![symbol]);
+        }
+        yields.insert(symbol, by_length);
+    }
+
+    // The yields of each length can use the yields of the same length
+    // through nullable symbols, so each length is iterated to a fixpoint.
+    for length in 0..=max_length {
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for rule in grammar.rules() {
+                let mut sentences = BTreeSet::new();
+                concatenations(&yields, &rule.rhs(), length, &mut vec![], &mut sentences);
+                let lhs_yields = &mut yields.get_mut(&rule.lhs()).unwrap()[length];
+                for sentence in sentences {
+                    changed |= lhs_yields.insert(sentence);
+                }
+            }
+        }
+    }
+
+    yields
+        .remove(&grammar.start_symbol())
+        .unwrap()
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+/// The number of sentences of `grammar` of each length up to `max_length`.
+///
+/// This enumerates the sentences, so it is only practical for small lengths.
+pub fn count_sentences(grammar: &Grammar, max_length: usize) -> Vec<u128> {
+    let mut counts = vec![0; max_length + 1];
+    for sentence in enumerate(grammar, max_length) {
+        counts[sentence.len()] += 1;
+    }
+    counts
+}
+
+/// The number of parse trees of `grammar` for sentences of each length up to `max_length`.
+///
+/// This counts by dynamic programming over the rules, without enumerating the sentences,
+/// so it is practical for large lengths.
+/// Counts which overflow saturate at `u128::MAX`,
+/// as do the infinite counts of grammars where a nonterminal can derive itself.
+pub fn count_derivations(grammar: &Grammar, max_length: usize) -> Vec<u128> {
+    let nonterminals = grammar.nonterminals();
+    let mut counts: BTreeMap<Symbol<'_>, Vec<u128>> = BTreeMap::new();
+    for symbol in grammar.symbols() {
+        let mut by_length = vec![0; max_length + 1];
+        if symbol.is_terminal() && max_length > 0 {
+            by_length[1] = 1;
+        }
+        counts.insert(symbol, by_length);
+    }
+
+    for length in 0..=max_length {
+        // Without a cycle, every count settles after one round per nonterminal.
+        // A count which is still growing after that goes through a cycle, and so is infinite.
+        let mut round = 0;
+        loop {
+            let mut changed = false;
+            for symbol in &nonterminals {
+                let count = symbol
+                    .rules()
+                    .into_iter()
+                    .map(|rule| rhs_derivations(&counts, &rule.rhs(), length))
+                    .fold(0u128, u128::saturating_add);
+                let old = counts[symbol][length];
+                if count != old {
+                    let count = if round > nonterminals.len() { u128::MAX } else { count };
+                    counts.get_mut(symbol).unwrap()[length] = count;
+                    changed = true;
+                }
+            }
+            if !changed {
+                break;
+            }
+            round += 1;
+        }
+    }
+
+    counts.remove(&grammar.start_symbol()).unwrap()
+}
+
+/// The shortest length up to `max_length` at which some sentence of `grammar` has more than one parse tree.
+///
+/// This compares `count_derivations` with `count_sentences`.
+/// `None` means the grammar is unambiguous for sentences up to `max_length`.
+pub fn first_ambiguous_length(grammar: &Grammar, max_length: usize) -> Option<usize> {
+    let sentences = count_sentences(grammar, max_length);
+    let derivations = count_derivations(grammar, max_length);
+    (0..=max_length).find(|length| derivations[*length] > sentences[*length])
+}
+
+// Add every concatenation of yields of `rhs` with a total of `length` terminals to `sentences`.
+fn concatenations<'g>(
+    yields: &BTreeMap<Symbol<'g>, Vec<BTreeSet<Vec<Symbol<'g>>>>>,
+    rhs: &[Symbol<'g>],
+    length: usize,
+    prefix: &mut Vec<Symbol<'g>>,
+    sentences: &mut BTreeSet<Vec<Symbol<'g>>>,
+) {
+    let Some((first, rest)) = rhs.split_first() else {
+        if length == 0 {
+            sentences.insert(prefix.clone());
+        }
+        return;
+    };
+
+    for first_length in 0..=length {
+        for sentence in &yields[first][first_length] {
+            let prefix_length = prefix.len();
+            prefix.extend(sentence);
+            concatenations(yields, rest, length - first_length, prefix, sentences);
+            prefix.truncate(prefix_length);
+        }
+    }
+}
+
+// The number of ways for `rhs` to derive strings of `length` terminals.
+fn rhs_derivations<'g>(counts: &BTreeMap<Symbol<'g>, Vec<u128>>, rhs: &[Symbol<'g>], length: usize) -> u128 {
+    // `ways[m]` is the number of ways for the symbols so far to derive `m` terminals.
+    let mut ways = vec![0u128; length + 1];
+    ways[0] = 1;
+    for symbol in rhs {
+        let symbol_counts = &counts[symbol];
+        let mut next = vec![0u128; length + 1];
+        for (m, count) in ways.iter().enumerate() {
+            if *count == 0 {
+                continue;
+            }
+            for (n, symbol_count) in symbol_counts[..=length - m].iter().enumerate() {
+                next[m + n] = next[m + n].saturating_add(count.saturating_mul(*symbol_count));
+            }
+        }
+        ways = next;
+    }
+    ways[length]
+}
@@ -0,0 +1,169 @@
+use std::collections::BTreeSet;
+
+use crate::*;
+use crate::lr1::{Action, ParseTable, StateIndex};
+
+/// An invalid sentence which differs from a valid one by a single token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NearMiss<'g> {
+    sentence: Vec<Symbol<'g>>,
+    mutation: Mutation<'g>,
+    error_position: usize,
+    expected: BTreeSet<Option<Symbol<'g>>>,
+}
+
+/// A change to a single token of a sentence.
+///
+/// Positions are indexes into the original sentence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mutation<'g> {
+    /// Remove the token at the position.
+    Delete(usize),
+
+    /// Insert a token before the position.
+    Insert(usize, Symbol<'g>),
+
+    /// Swap the token at the position with the one after it.
+    Swap(usize),
+
+    /// Replace the token at the position with another.
+    Replace(usize, Symbol<'g>),
+}
+
+impl<'g> NearMiss<'g> {
+    /// The invalid sentence.
+    pub fn sentence(&self) -> &[Symbol<'g>] {
+        &self.sentence
+    }
+
+    /// The mutation which made the invalid sentence from the valid one.
+    pub fn mutation(&self) -> Mutation<'g> {
+        self.mutation
+    }
+
+    /// The position in the invalid sentence of the token where the parser detects the error.
+    ///
+    /// This is the length of the sentence if the error is an unexpected end of input.
+    pub fn error_position(&self) -> usize {
+        self.error_position
+    }
+
+    /// The tokens the parser would have accepted at the error position.
+    ///
+    /// `None` is the end of input.
+    pub fn expected(&self) -> &BTreeSet<Option<Symbol<'g>>> {
+        &self.expected
+    }
+}
+
+/// Generate the invalid sentences which differ from `sentence` by a single token.
+///
+/// Every deletion, insertion, swap of adjacent tokens and replacement is tried, in that order,
+/// and the mutants which `table` rejects are kept, each with where the parser detects the error
+/// and which tokens it expected there.
+/// A mutant which more than one mutation produces is only reported for the first.
+///
+/// # Panics
+///
+/// Panics if `table` has conflicts.
+pub fn near_misses<'g>(table: &ParseTable<'g>, sentence: &[Symbol<'g>]) -> Vec<NearMiss<'g>> {
+    assert!(table.conflicts().is_empty(), "Parse table has conflicts");
+    let terminals: Vec<Symbol<'g>> = table.grammar().symbols().into_iter().filter(|symbol| symbol.is_terminal()).collect();
+
+    let mut mutations = vec![];
+    for position in 0..sentence.len() {
+        mutations.push(Mutation::Delete(position));
+    }
+    for position in 0..=sentence.len() {
+        for terminal in &terminals {
+            mutations.push(Mutation::Insert(position, *terminal));
+        }
+    }
+    for position in 1..sentence.len() {
+        if sentence[position - 1] != sentence[position] {
+            mutations.push(Mutation::Swap(position - 1));
+        }
+    }
+    for (position, symbol) in sentence.iter().enumerate() {
+        for terminal in &terminals {
+            if terminal != symbol {
+                mutations.push(Mutation::Replace(position, *terminal));
+            }
+        }
+    }
+
+    let mut seen = BTreeSet::new();
+    let mut near_misses = vec![];
+    for mutation in mutations {
+        let mutant = mutation.apply(sentence);
+        if !seen.insert(mutant.clone()) {
+            continue;
+        }
+        if let Some((error_position, expected)) = parse_error(table, &mutant) {
+            near_misses.push(NearMiss {
+                sentence: mutant,
+                mutation,
+                error_position,
+                expected,
+            });
+        }
+    }
+    near_misses
+}
+
+impl<'g> Mutation<'g> {
+    /// Apply the mutation to `sentence`.
+    pub fn apply(&self, sentence: &[Symbol<'g>]) -> Vec<Symbol<'g>> {
+        let mut sentence = sentence.to_vec();
+        match *self {
+            Mutation::Delete(position) => {
+                sentence.remove(position);
+            }
+            Mutation::Insert(position, symbol) => sentence.insert(position, symbol),
+            Mutation::Swap(position) => sentence.swap(position, position + 1),
+            Mutation::Replace(position, symbol) => sentence[position] = symbol,
+        }
+        sentence
+    }
+}
+
+// Run the parser over `input`.
+// If it rejects it, return the position of the token it stopped at
+// and the tokens it would have accepted there.
+fn parse_error<'g>(table: &ParseTable<'g>, input: &[Symbol<'g>]) -> Option<(usize, BTreeSet<Option<Symbol<'g>>>)> {
+    let grammar = table.grammar();
+    let mut stack = vec![StateIndex(0)];
+    let mut position = 0;
+    loop {
+        let state = *stack.last().unwrap();
+        let actions = table.get(state, input.get(position).copied());
+        match actions.first() {
+            None => {
+                let expected = grammar
+                    .symbols()
+                    .into_iter()
+                    .filter(|symbol| symbol.is_terminal())
+                    .map(Some)
+                    .chain([None])
+                    .filter(|symbol| !table.get(state, *symbol).is_empty())
+                    .collect();
+                return Some((position, expected));
+            }
+            Some(Action::Shift(next)) => {
+                stack.push(*next);
+                position += 1;
+            }
+            Some(Action::Reduce(rule)) => {
+                if *rule == grammar.start_rule() {
+                    return None;
+                }
+                stack.truncate(stack.len() - rule.rhs().len());
+                let state = *stack.last().unwrap();
+                match table.get(state, Some(rule.lhs()))[..] {
+                    [Action::Shift(next)] => stack.push(next),
+                    ref actions => panic!("Expected GOTO but found: {actions:?}"),
+                }
+            }
+        }
+    }
+}
@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 // Offset into Grammar::symbols
 #[derive(Clone, Copy, Eq, PartialEq, Debug, Ord, PartialOrd)]
 pub struct SymbolIndex(usize);
@@ -21,6 +23,7 @@ impl From<RuleIndex> for usize {
 pub struct GrammarBuilder {
     symbols: Vec<SymbolData>,
     rules: Vec<RuleData>,
+    symbol_indices: HashMap<String, SymbolIndex>,
 }
 
 /// `Grammar` represents a context-free grammar.
@@ -29,12 +32,17 @@ pub struct GrammarBuilder {
 pub struct Grammar {
     symbols: Vec<SymbolData>,
     rules: Vec<RuleData>,
+    symbol_indices: HashMap<String, SymbolIndex>,
 }
 
 // Symbols should have a name.
 // Ideally, these should be a C-style identifier.
 struct SymbolData {
     name: String,
+    // The rules with this symbol on the LHS.
+    // This is filled in by `GrammarBuilder::build`.
+    // A symbol is a terminal exactly when this is empty.
+    rules: Vec<RuleIndex>,
 }
 
 // A production rule takes a nonterminal symbol on the LHS
@@ -139,6 +147,10 @@ impl<'g> Symbol<'g> {
         self.grammar
     }
 
+    pub fn index(&self) -> SymbolIndex {
+        self.index
+    }
+
     fn data(&self) -> &SymbolData {
         &self.grammar.symbols[self.index.0]
     }
@@ -155,12 +167,21 @@ impl<'g> Symbol<'g> {
 
     /// Is the symbol a nonterminal?
     pub fn is_nonterminal(&self) -> bool {
-        for rule in self.grammar.rules() {
-            if rule.lhs() == *self {
-                return true;
-            }
+        !self.data().rules.is_empty()
+    }
+
+    /// The rules with this symbol on the LHS.
+    ///
+    /// This is empty for terminals.
+    pub fn rules(&self) -> Vec<Rule<'g>> {
+        let mut result = vec![];
+        for rule in &self.data().rules {
+            result.push(Rule {
+                grammar: self.grammar,
+                index: *rule,
+            });
         }
-        false
+        result
     }
 }
 
@@ -209,6 +230,7 @@ impl Grammar {
         GrammarBuilder {
             symbols: vec![],
             rules: vec![],
+            symbol_indices: HashMap::new(),
         }
     }
 
@@ -269,17 +291,20 @@ impl Grammar {
         result
     }
 
+    /// The set of rules with `symbol` on the LHS.
+    pub fn rules_for<'g>(&'g self, symbol: Symbol<'g>) -> Vec<Rule<'g>> {
+        assert!(std::ptr::eq(self, symbol.grammar), "Symbol belongs to a different grammar: {symbol:?}");
+        symbol.rules()
+    }
+
     /// Fetch the symbol with the given name if it exists.
     pub fn symbol<S: AsRef<str>>(&self, name: S) -> Option<Symbol<'_>> {
-        for (index, symbol) in self.symbols.iter().enumerate() {
-            if symbol.name == name.as_ref() {
-                return Some(Symbol {
-                    grammar: self,
-                    index: SymbolIndex(index),
-                });
+        self.symbol_indices.get(name.as_ref()).map(|index| {
+            Symbol {
+                grammar: self,
+                index: *index,
             }
-        }
-        None
+        })
     }
 }
 
@@ -287,9 +312,11 @@ impl GrammarBuilder {
     /// Declare a symbol.
     pub fn symbol<S: Into<String>>(mut self, name: S) -> Self {
         let name: String = name.into();
-        assert!(!self.symbols.iter().any(|symbol_data| symbol_data.name == name), "Symbol declared twice: {name}");
+        assert!(!self.symbol_indices.contains_key(&name), "Symbol declared twice: {name}");
+        self.symbol_indices.insert(name.clone(), SymbolIndex(self.symbols.len()));
         self.symbols.push(SymbolData {
             name,
+            rules: vec![],
         });
         self
     }
@@ -306,22 +333,26 @@ impl GrammarBuilder {
     }
 
     /// Finish building this object and return the result as a `Grammar`.
-    pub fn build(self) -> Grammar {
+    pub fn build(mut self) -> Grammar {
         let start_rule = &self.rules[0];
         assert_eq!(start_rule.rhs.len(), 1);
 
+        // Index the rules by their LHS.
+        for (index, rule) in self.rules.iter().enumerate() {
+            self.symbols[rule.lhs.0].rules.push(RuleIndex(index));
+        }
+
         Grammar {
             symbols: self.symbols,
             rules: self.rules,
+            symbol_indices: self.symbol_indices,
         }
     }
 
     fn symbol_index(&self, symbol_name: &str) -> SymbolIndex {
-        for (i, symbol) in self.symbols.iter().enumerate() {
-            if symbol.name == symbol_name {
-                return SymbolIndex(i);
-            }
+        match self.symbol_indices.get(symbol_name) {
+            Some(index) => *index,
+            None => panic!("No such symbol: {symbol_name}"),
         }
-        panic!("No such symbol: {symbol_name}")
     }
 }
@@ -0,0 +1,82 @@
+mod intersect;
+mod combinators;
+
+use std::collections::BTreeSet;
+
+use crate::*;
+use crate::generate::enumerate;
+
+/// The result of comparing the languages of two grammars with `compare_languages`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LanguageComparison<'a, 'b> {
+    max_length: usize,
+    only_in_first: Option<Vec<Symbol<'a>>>,
+    only_in_second: Option<Vec<Symbol<'b>>>,
+}
+
+/// Compare the languages of two grammars on every sentence of up to `max_length` terminals.
+///
+/// Terminals are matched by name, so the grammars may be otherwise unrelated.
+/// A sentence which uses a terminal the other grammar lacks is in one language but not the other.
+pub fn compare_languages<'a, 'b>(first: &'a Grammar, second: &'b Grammar, max_length: usize) -> LanguageComparison<'a, 'b> {
+    let first_sentences = enumerate(first, max_length);
+    let second_sentences = enumerate(second, max_length);
+
+    let first_names: BTreeSet<Vec<String>> = first_sentences.iter().map(|sentence| names(sentence)).collect();
+    let second_names: BTreeSet<Vec<String>> = second_sentences.iter().map(|sentence| names(sentence)).collect();
+
+    // The sentences are in shortlex order, so the first one missing from the other language is a shortest one.
+    LanguageComparison {
+        max_length,
+        only_in_first: first_sentences.into_iter().find(|sentence| !second_names.contains(&names(sentence))),
+        only_in_second: second_sentences.into_iter().find(|sentence| !first_names.contains(&names(sentence))),
+    }
+}
+
+impl<'a, 'b> LanguageComparison<'a, 'b> {
+    /// The length of the longest sentences compared.
+    pub fn max_length(&self) -> usize {
+        self.max_length
+    }
+
+    /// Do the grammars have the same sentences, up to the maximum length?
+    pub fn is_equal(&self) -> bool {
+        self.only_in_first.is_none() && self.only_in_second.is_none()
+    }
+
+    /// Is every sentence of the first grammar a sentence of the second, up to the maximum length?
+    pub fn is_first_included(&self) -> bool {
+        self.only_in_first.is_none()
+    }
+
+    /// Is every sentence of the second grammar a sentence of the first, up to the maximum length?
+    pub fn is_second_included(&self) -> bool {
+        self.only_in_second.is_none()
+    }
+
+    /// A shortest sentence of the first grammar which is not a sentence of the second.
+    pub fn only_in_first(&self) -> Option<&[Symbol<'a>]> {
+        self.only_in_first.as_deref()
+    }
+
+    /// A shortest sentence of the second grammar which is not a sentence of the first.
+    pub fn only_in_second(&self) -> Option<&[Symbol<'b>]> {
+        self.only_in_second.as_deref()
+    }
+
+    /// A shortest sentence in one language but not the other, by the names of its terminals.
+    ///
+    /// When there is one of the same length in each direction, the one from the first grammar is returned.
+    pub fn distinguishing_sentence(&self) -> Option<Vec<String>> {
+        match (&self.only_in_first, &self.only_in_second) {
+            (Some(first), Some(second)) if second.len() < first.len() => Some(names(second)),
+            (Some(first), _) => Some(names(first)),
+            (None, Some(second)) => Some(names(second)),
+            (None, None) => None,
+        }
+    }
+}
+
+fn names(sentence: &[Symbol<'_>]) -> Vec<String> {
+    sentence.iter().map(|symbol| symbol.name()).collect()
+}
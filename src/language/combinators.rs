@@ -0,0 +1,168 @@
+use std::collections::BTreeMap;
+
+use crate::*;
+use crate::transform::{Draft, Provenance};
+
+impl Grammar {
+    /// A grammar for the sentences of either grammar.
+    ///
+    /// The new start rule is `Start -> Union`, with `Union -> S1` and `Union -> S2` for the start symbols of the grammars.
+    /// Terminals with the same name are the same terminal.
+    /// Every other symbol keeps its name unless it is already taken,
+    /// in which case it is renamed by adding the prefix `first_` or `second_` to the front until it isn't.
+    pub fn union(&self, other: &Grammar) -> Grammar {
+        let mut draft = draft_for(&[self, other]);
+        let first = rename(&mut draft, self, "first_");
+        let second = rename(&mut draft, other, "second_");
+        let union = start_rule(&mut draft, "Union");
+        draft.rule(union.clone(), vec![first[&self.start_symbol()].clone()], Provenance::none());
+        draft.rule(union, vec![second[&other.start_symbol()].clone()], Provenance::none());
+        copy_rules(&mut draft, self, &first);
+        copy_rules(&mut draft, other, &second);
+        draft.grammar()
+    }
+
+    /// A grammar for a sentence of this grammar followed by a sentence of `other`.
+    ///
+    /// The new start rule is `Start -> Concat`, with `Concat -> S1 S2`.
+    /// Symbols are renamed as in `union`.
+    pub fn concat(&self, other: &Grammar) -> Grammar {
+        let mut draft = draft_for(&[self, other]);
+        let first = rename(&mut draft, self, "first_");
+        let second = rename(&mut draft, other, "second_");
+        let concat = start_rule(&mut draft, "Concat");
+        let rhs = vec![first[&self.start_symbol()].clone(), second[&other.start_symbol()].clone()];
+        draft.rule(concat, rhs, Provenance::none());
+        copy_rules(&mut draft, self, &first);
+        copy_rules(&mut draft, other, &second);
+        draft.grammar()
+    }
+
+    /// A grammar for sequences of zero or more sentences of this grammar.
+    ///
+    /// The new start rule is `Start -> Star`, with `Star ->` and `Star -> Star S`.
+    pub fn star(&self) -> Grammar {
+        let mut draft = draft_for(&[self]);
+        let names = rename(&mut draft, self, "star_");
+        let star = start_rule(&mut draft, "Star");
+        draft.rule(star.clone(), vec![], Provenance::none());
+        draft.rule(star.clone(), vec![star, names[&self.start_symbol()].clone()], Provenance::none());
+        copy_rules(&mut draft, self, &names);
+        draft.grammar()
+    }
+
+    /// A grammar for the reversals of the sentences of this grammar.
+    ///
+    /// It has the same symbols, and each rule has its RHS reversed.
+    pub fn reversed(&self) -> Grammar {
+        let mut builder = Grammar::new();
+        for symbol in self.symbols() {
+            builder = builder.symbol(symbol.name());
+        }
+        for rule in self.rules() {
+            let rhs: Vec<String> = rule.rhs().iter().rev().map(|symbol| symbol.name()).collect();
+            builder = builder.rule(rule.lhs().name(), &rhs);
+        }
+        builder.build()
+    }
+
+    /// Replace the terminal `terminal` with the sentences of `replacement`.
+    ///
+    /// Each occurrence of the terminal becomes the start symbol of `replacement`.
+    /// Symbols are renamed as in `union`, with the prefixes `outer_` and `{terminal}_`.
+    pub fn substitute(&self, terminal: &str, replacement: &Grammar) -> Grammar {
+        let terminal_symbol = match self.symbol(terminal) {
+            Some(symbol) if symbol.is_terminal() => symbol,
+            _ => panic!("No such terminal: {terminal}"),
+        };
+
+        let mut draft = draft_for(&[self, replacement]);
+        let mut outer = rename(&mut draft, self, "outer_");
+        let inner = rename(&mut draft, replacement, &format!("{terminal}_"));
+
+        outer.insert(terminal_symbol, inner[&replacement.start_symbol()].clone());
+        copy_rules(&mut draft, self, &outer);
+        copy_rules(&mut draft, replacement, &inner);
+        draft.grammar()
+    }
+
+    /// Replace the terminal `terminal` with the symbols named by `form`.
+    ///
+    /// Names in `form` which aren't symbols of this grammar become new terminals.
+    /// When `form` consists of terminals, this is the image under a homomorphism which only changes `terminal`.
+    /// If the start rule `S -> X` would no longer have a single symbol on its RHS,
+    /// a new start rule `Start -> S` is added.
+    pub fn substitute_form(&self, terminal: &str, form: &[&str]) -> Grammar {
+        assert!(self.symbol(terminal).is_some_and(|symbol| symbol.is_terminal()), "No such terminal: {terminal}");
+
+        let mut draft = Draft::new(self);
+        for name in form {
+            draft.mention(name);
+        }
+        let substituted = |rule: Rule<'_>| -> Vec<String> {
+            rule.rhs()
+                .iter()
+                .flat_map(|symbol| {
+                    if symbol.name() == terminal {
+                        form.iter().map(|name| name.to_string()).collect()
+                    } else {
+                        vec![symbol.name()]
+                    }
+                })
+                .collect()
+        };
+
+        let start_rule = self.start_rule();
+        if substituted(start_rule).len() != 1 {
+            let start = draft.unused("Start");
+            draft.rule(start, vec![start_rule.lhs().name()], Provenance::none());
+        }
+        for rule in self.rules() {
+            draft.rule(rule.lhs().name(), substituted(rule), Provenance::none());
+        }
+        draft.grammar()
+    }
+}
+
+// A draft in which the names of the terminals of every grammar are taken.
+fn draft_for(grammars: &[&Grammar]) -> Draft {
+    let mut draft = Draft::empty();
+    for terminal in grammars.iter().flat_map(|grammar| grammar.terminals()) {
+        draft.mention(&terminal.name());
+    }
+    draft
+}
+
+// The new name of each symbol of `grammar`.
+// Terminals keep their names.
+// Each nonterminal whose name is taken is renamed by adding `prefix` to the front until it isn't.
+fn rename<'g>(draft: &mut Draft, grammar: &'g Grammar, prefix: &str) -> BTreeMap<Symbol<'g>, String> {
+    grammar
+        .symbols()
+        .into_iter()
+        .map(|symbol| {
+            let name = if symbol.is_terminal() {
+                symbol.name()
+            } else {
+                draft.prefixed(prefix, &symbol.name())
+            };
+            (symbol, name)
+        })
+        .collect()
+}
+
+// Add the rules of `grammar`, with the symbols given their new names.
+fn copy_rules<'g>(draft: &mut Draft, grammar: &'g Grammar, names: &BTreeMap<Symbol<'g>, String>) {
+    for rule in grammar.rules() {
+        let rhs = rule.rhs().iter().map(|symbol| names[symbol].clone()).collect();
+        draft.rule(names[&rule.lhs()].clone(), rhs, Provenance::none());
+    }
+}
+
+// Add the start rule `Start -> body`, where `body` is a new symbol based on `name`, and return the name of `body`.
+fn start_rule(draft: &mut Draft, name: &str) -> String {
+    let start = draft.unused("Start");
+    let body = draft.unused(name);
+    draft.rule(start, vec![body.clone()], Provenance::none());
+    body
+}
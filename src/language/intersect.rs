@@ -0,0 +1,142 @@
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use crate::*;
+use crate::dfa::{Dfa, StateIdx};
+use crate::transform::{Draft, Provenance};
+
+impl Grammar {
+    /// A grammar for the sentences of this grammar which `dfa` accepts.
+    ///
+    /// The DFA starts in state 0, has a column for each symbol of this grammar,
+    /// and reads the indexes of the terminals, like the DFA of `GrammarAnalysis::regular_approximation_dfa`.
+    /// It accepts in the states of `accepting`.
+    ///
+    /// This is the Bar-Hillel construction.
+    /// Each nonterminal `A` becomes a nonterminal `A_p_q` for each pair of states
+    /// such that `A` derives a string which takes the DFA from `p` to `q`.
+    /// The start rule `S -> X` becomes `S -> X_accepted`, with `X_accepted -> X_0_q` for each accepting state `q`.
+    /// Only the useful symbols are kept.
+    /// When the intersection is empty, `X_accepted` is given the single rule `X_accepted -> X_accepted`, as in `reduced`.
+    pub fn intersect(&self, dfa: &Dfa, accepting: &BTreeSet<StateIdx>) -> Grammar {
+        assert_eq!(dfa.num_symbols(), self.symbols().len(), "The DFA needs a column for each symbol");
+
+        // For each symbol and state, the states reached after reading a string the symbol derives.
+        let mut ends: BTreeMap<Symbol<'_>, Vec<BTreeSet<StateIdx>>> = BTreeMap::new();
+        for symbol in self.symbols() {
+            let by_state = (0..dfa.num_states())
+                .map(|state| {
+                    if symbol.is_terminal() {
+                        BTreeSet::from([dfa.transition(state, usize::from(symbol.index()))])
+                    } else {
+                        BTreeSet::new()
+                    }
+                })
+                .collect();
+            ends.insert(symbol, by_state);
+        }
+
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for rule in self.rules() {
+                for state in 0..dfa.num_states() {
+                    let mut states = BTreeSet::from([state]);
+                    for symbol in rule.rhs() {
+                        states = states.iter().flat_map(|state| ends[&symbol][*state].iter().copied()).collect();
+                    }
+                    let lhs_ends = &mut ends.get_mut(&rule.lhs()).unwrap()[state];
+                    for end in states {
+                        changed |= lhs_ends.insert(end);
+                    }
+                }
+            }
+        }
+
+        let mut names = Names {
+            draft: Draft::new(self),
+            triples: BTreeMap::new(),
+        };
+        let start_rule = self.start_rule();
+        let start = start_rule.rhs()[0];
+        let accepted = names.draft.fresh(&start.name(), "accepted");
+        names.draft.rule(start_rule.lhs().name(), vec![accepted.clone()], Provenance::none());
+
+        // Only the triples reachable from the start are given rules.
+        let mut queue = VecDeque::new();
+        for end in &ends[&start][0] {
+            if accepting.contains(end) {
+                let triple = names.triple(0, start, *end);
+                names.draft.rule(accepted.clone(), vec![triple], Provenance::none());
+                queue.push_back((0, start, *end));
+            }
+        }
+        if queue.is_empty() {
+            names.draft.rule(accepted.clone(), vec![accepted.clone()], Provenance::none());
+        }
+
+        let mut visited: BTreeSet<(StateIdx, Symbol<'_>, StateIdx)> = queue.iter().copied().collect();
+        while let Some((from, symbol, to)) = queue.pop_front() {
+            let lhs = names.triple(from, symbol, to);
+            for rule in symbol.rules() {
+                let mut paths = vec![];
+                paths_through(&ends, &rule.rhs(), from, to, &mut vec![], &mut paths);
+                for path in paths {
+                    let mut rhs = vec![];
+                    for (i, child) in rule.rhs().into_iter().enumerate() {
+                        if child.is_terminal() {
+                            rhs.push(child.name());
+                        } else {
+                            let triple = (path[i], child, path[i + 1]);
+                            rhs.push(names.triple(triple.0, triple.1, triple.2));
+                            if visited.insert(triple) {
+                                queue.push_back(triple);
+                            }
+                        }
+                    }
+                    names.draft.rule(lhs.clone(), rhs, Provenance::none());
+                }
+            }
+        }
+
+        names.draft.grammar()
+    }
+}
+
+// The names of the new nonterminals, which mustn't clash with those of the original grammar.
+struct Names<'g> {
+    draft: Draft,
+    triples: BTreeMap<(StateIdx, Symbol<'g>, StateIdx), String>,
+}
+
+impl<'g> Names<'g> {
+    fn triple(&mut self, from: StateIdx, symbol: Symbol<'g>, to: StateIdx) -> String {
+        if let Some(name) = self.triples.get(&(from, symbol, to)) {
+            return name.clone();
+        }
+        let name = self.draft.fresh(&symbol.name(), &format!("{from}_{to}"));
+        self.triples.insert((from, symbol, to), name.clone());
+        name
+    }
+}
+
+// Add every sequence of states, from `from` to `to`, which `rhs` can take the DFA through to `paths`.
+fn paths_through<'g>(
+    ends: &BTreeMap<Symbol<'g>, Vec<BTreeSet<StateIdx>>>,
+    rhs: &[Symbol<'g>],
+    from: StateIdx,
+    to: StateIdx,
+    path: &mut Vec<StateIdx>,
+    paths: &mut Vec<Vec<StateIdx>>,
+) {
+    path.push(from);
+    match rhs.split_first() {
+        None if from == to => paths.push(path.clone()),
+        None => (),
+        Some((first, rest)) => {
+            for next in &ends[first][from] {
+                paths_through(ends, rest, *next, to, path, paths);
+            }
+        }
+    }
+    path.pop();
+}
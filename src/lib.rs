@@ -2,15 +2,24 @@
 mod tests;
 
 pub mod ll1;
+pub mod llk;
 pub mod lr0;
 pub mod lr1;
+pub mod lrk;
 
 mod macros;
 mod grammar;
 mod analysis;
+mod tree;
+mod transform;
 
 pub use grammar::{Grammar, Rule, Symbol, RuleIndex, SymbolIndex};
-pub use analysis::GrammarAnalysis;
+pub use analysis::{GrammarAnalysis, DependencyGraph, Recursion, RecursionKind};
+pub use tree::ParseTree;
+pub use transform::Transformed;
 
+pub mod ambiguity;
+pub mod generate;
+pub mod language;
 pub mod dfa;
 pub mod nfa;
@@ -1,5 +1,9 @@
 mod table;
 mod machine;
+mod conflict;
+mod recovery;
 
 pub use table::ParseTable;
-pub use machine::Machine;
+pub use machine::{Machine, Visitor, ParseError};
+pub use conflict::{Conflict, ConflictKind};
+pub use recovery::{Recovery, Diagnostic, Repair};
@@ -0,0 +1,225 @@
+use std::collections::BTreeSet;
+
+use crate::*;
+use super::*;
+
+/// Information on a conflict found in an LL(1) parse table.
+///
+/// A conflict is a pair of rules for the same nonterminal
+/// which are both predicted on the same lookahead.
+#[derive(Clone)]
+pub struct Conflict<'g> {
+    nonterminal: Symbol<'g>,
+    lookahead: Option<Symbol<'g>>,
+    kind: ConflictKind,
+    rules: Vec<Rule<'g>>,
+    witnesses: Vec<Vec<Rule<'g>>>,
+}
+
+/// The cause of an LL(1) conflict.
+#[derive(Debug)]
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ConflictKind {
+    /// The lookahead is in the FIRST set of both rules.
+    FirstFirst,
+
+    /// At least one of the rules is nullable,
+    /// and the lookahead is in the FOLLOW set of the nonterminal.
+    FirstFollow,
+}
+
+impl<'g> Conflict<'g> {
+    pub(crate) fn new(
+        table: &ParseTable<'g>,
+        nonterminal: Symbol<'g>,
+        lookahead: Option<Symbol<'g>>,
+        rule1: Rule<'g>,
+        rule2: Rule<'g>,
+    ) -> Conflict<'g> {
+        let analysis = table.analysis();
+        let predicted_by_first = |rule: Rule<'g>| {
+            lookahead.is_some_and(|terminal| analysis.first_seq(&rule.rhs()).contains(&terminal))
+        };
+
+        let kind = if predicted_by_first(rule1) && predicted_by_first(rule2) {
+            ConflictKind::FirstFirst
+        } else {
+            ConflictKind::FirstFollow
+        };
+
+        let witnesses = [rule1, rule2]
+            .into_iter()
+            .map(|rule| {
+                let mut witness = vec![rule];
+                if predicted_by_first(rule) {
+                    let terminal = lookahead.unwrap();
+                    witness.extend(first_witness(analysis, &rule.rhs(), terminal, &mut BTreeSet::new()).unwrap());
+                } else {
+                    witness.extend(nullable_seq_witness(analysis, &rule.rhs()).unwrap());
+                    let follow = follow_witness(analysis, table.start_symbol(), nonterminal, lookahead, &mut BTreeSet::new());
+                    witness.extend(follow.unwrap());
+                }
+                witness
+            })
+            .collect();
+
+        Conflict {
+            nonterminal,
+            lookahead,
+            kind,
+            rules: vec![rule1, rule2],
+            witnesses,
+        }
+    }
+
+    /// The nonterminal on top of the stack.
+    pub fn nonterminal(&self) -> Symbol<'g> {
+        self.nonterminal
+    }
+
+    /// The lookahead symbol, or `None` for EOF.
+    pub fn lookahead(&self) -> Option<Symbol<'g>> {
+        self.lookahead
+    }
+
+    pub fn kind(&self) -> ConflictKind {
+        self.kind
+    }
+
+    /// The two competing rules.
+    pub fn rules(&self) -> &[Rule<'g>] {
+        &self.rules
+    }
+
+    /// A witness for each of the competing rules, in the same order as `rules`.
+    ///
+    /// Each witness starts with the rule itself.
+    /// When the rule is predicted through FIRST, the rest is a leftmost derivation
+    /// of the RHS into a string starting with the lookahead.
+    /// Otherwise, the rest derives the RHS to ε,
+    /// followed by the rules which place the lookahead after the nonterminal.
+    pub fn witnesses(&self) -> &[Vec<Rule<'g>>] {
+        &self.witnesses
+    }
+}
+
+impl<'g> std::fmt::Debug for Conflict<'g> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let nonterminal = self.nonterminal;
+        let lookahead = self.lookahead;
+        let kind = self.kind;
+        let rules = &self.rules;
+        write!(f, "Conflict(nonterminal={nonterminal:?}, lookahead={lookahead:?}, kind={kind:?}, rules={rules:?})")?;
+        Ok(())
+    }
+}
+
+// A leftmost derivation of `seq` into a string beginning with `terminal`.
+// `visiting` holds the nonterminals currently being expanded, to avoid looping on left recursion.
+fn first_witness<'g>(
+    analysis: &GrammarAnalysis<'g>,
+    seq: &[Symbol<'g>],
+    terminal: Symbol<'g>,
+    visiting: &mut BTreeSet<Symbol<'g>>,
+) -> Option<Vec<Rule<'g>>> {
+    let mut derivation = vec![];
+    for symbol in seq.iter().copied() {
+        if symbol.is_terminal() {
+            return if symbol == terminal { Some(derivation) } else { None };
+        }
+
+        if analysis.first(symbol).contains(&terminal) && visiting.insert(symbol) {
+            let found = symbol.rules().into_iter().find_map(|rule| {
+                let rest = first_witness(analysis, &rule.rhs(), terminal, visiting)?;
+                Some([rule].into_iter().chain(rest).collect::<Vec<_>>())
+            });
+            visiting.remove(&symbol);
+
+            if let Some(rest) = found {
+                derivation.extend(rest);
+                return Some(derivation);
+            }
+        }
+
+        if !analysis.is_nullable(symbol) {
+            return None;
+        }
+        derivation.extend(nullable_witness(analysis, symbol, &mut BTreeSet::new())?);
+    }
+    None
+}
+
+// A leftmost derivation of each of the symbols of `seq` into ε.
+fn nullable_seq_witness<'g>(analysis: &GrammarAnalysis<'g>, seq: &[Symbol<'g>]) -> Option<Vec<Rule<'g>>> {
+    let mut derivation = vec![];
+    for symbol in seq {
+        derivation.extend(nullable_witness(analysis, *symbol, &mut BTreeSet::new())?);
+    }
+    Some(derivation)
+}
+
+// A leftmost derivation of `symbol` into ε.
+fn nullable_witness<'g>(
+    analysis: &GrammarAnalysis<'g>,
+    symbol: Symbol<'g>,
+    visiting: &mut BTreeSet<Symbol<'g>>,
+) -> Option<Vec<Rule<'g>>> {
+    if !analysis.is_nullable(symbol) || !visiting.insert(symbol) {
+        return None;
+    }
+
+    let found = symbol.rules().into_iter().find_map(|rule| {
+        let mut derivation = vec![rule];
+        for rhs_symbol in rule.rhs() {
+            derivation.extend(nullable_witness(analysis, rhs_symbol, visiting)?);
+        }
+        Some(derivation)
+    });
+    visiting.remove(&symbol);
+    found
+}
+
+// The rules which place `lookahead` after `symbol`, innermost first.
+// Each rule has `symbol` (or the LHS of the previous rule) on its RHS,
+// followed either by something which derives a string starting with `lookahead`,
+// or by something nullable, in which case the chain continues with the LHS.
+// A `None` lookahead is reached by ending at `start_symbol`.
+fn follow_witness<'g>(
+    analysis: &GrammarAnalysis<'g>,
+    start_symbol: Symbol<'g>,
+    symbol: Symbol<'g>,
+    lookahead: Option<Symbol<'g>>,
+    visiting: &mut BTreeSet<Symbol<'g>>,
+) -> Option<Vec<Rule<'g>>> {
+    if lookahead.is_none() && symbol == start_symbol {
+        return Some(vec![]);
+    }
+
+    if !visiting.insert(symbol) {
+        return None;
+    }
+
+    let mut found = None;
+    'search: for rule in symbol.grammar().rules() {
+        let rhs = rule.rhs();
+        for (i, rhs_symbol) in rhs.iter().enumerate() {
+            if *rhs_symbol != symbol {
+                continue;
+            }
+
+            let rest = &rhs[i + 1..];
+            if let Some(terminal) = lookahead && let Some(derivation) = first_witness(analysis, rest, terminal, &mut BTreeSet::new()) {
+                found = Some([rule].into_iter().chain(derivation).collect());
+                break 'search;
+            }
+
+            if let Some(derivation) = nullable_seq_witness(analysis, rest) && let Some(outer) = follow_witness(analysis, start_symbol, rule.lhs(), lookahead, visiting) {
+                found = Some([rule].into_iter().chain(derivation).chain(outer).collect());
+                break 'search;
+            }
+        }
+    }
+
+    visiting.remove(&symbol);
+    found
+}
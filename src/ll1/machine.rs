@@ -1,64 +1,298 @@
+use std::collections::BTreeSet;
 use std::iter::Peekable;
 
 use crate::*;
 use super::*;
 
-pub struct Machine<'g, I>
+/// A machine which runs an LL(1) parse table against an input stream.
+pub struct Machine<'g, 't, I>
     where I: Iterator<Item=Symbol<'g>> {
-    table: ParseTable<'g>,
-    stack: Vec<Symbol<'g>>,
+    table: &'t ParseTable<'g>,
+    stack: Vec<Frame<'g>>,
     input: Peekable<I>,
+    position: usize,
 }
 
-impl<'g, I> Machine<'g, I> where I: Iterator<Item=Symbol<'g>> {
-    pub fn new(table: ParseTable<'g>, start_symbol: Symbol<'g>, input: I) -> Machine<'g, I> {
+// An entry on the machine's stack.
+#[derive(Clone, Copy)]
+enum Frame<'g> {
+    // A symbol still to be parsed.
+    Symbol(Symbol<'g>),
+    // A marker which is popped once every symbol of the rule's RHS has been parsed.
+    Exit(Rule<'g>),
+}
+
+/// A visitor which is fed the parse tree top-down as the machine runs.
+///
+/// All of the methods do nothing by default.
+pub trait Visitor<'g> {
+    /// Called when the machine predicts `rule`, before any of its RHS is parsed.
+    fn enter(&mut self, _rule: Rule<'g>) {}
+
+    /// Called once every symbol of the RHS of `rule` has been parsed.
+    fn exit(&mut self, _rule: Rule<'g>) {}
+
+    /// Called when a terminal is consumed from the input.
+    fn token(&mut self, _symbol: Symbol<'g>) {}
+}
+
+/// An error encountered while running an LL(1) machine.
+#[derive(Debug)]
+#[derive(Clone, PartialEq, Eq)]
+pub enum ParseError<'g> {
+    /// The next token can't be parsed.
+    ///
+    /// `found` is `None` at the end of the input.
+    /// `expected` holds the tokens which would have been accepted instead, with `None` standing for EOF.
+    UnexpectedToken {
+        position: usize,
+        found: Option<Symbol<'g>>,
+        expected: BTreeSet<Option<Symbol<'g>>>,
+    },
+
+    /// More than one rule is predicted for the nonterminal on top of the stack.
+    Conflict {
+        position: usize,
+        nonterminal: Symbol<'g>,
+        lookahead: Option<Symbol<'g>>,
+        rules: Vec<Rule<'g>>,
+    },
+}
+
+impl<'g> ParseError<'g> {
+    /// The number of tokens consumed before the error.
+    pub fn position(&self) -> usize {
+        match self {
+            ParseError::UnexpectedToken { position, .. } => *position,
+            ParseError::Conflict { position, .. } => *position,
+        }
+    }
+}
+
+impl<'g, 't, I> Machine<'g, 't, I> where I: Iterator<Item=Symbol<'g>> {
+    /// Create a machine which parses `input` starting from the table's start symbol.
+    pub fn new(table: &'t ParseTable<'g>, input: I) -> Machine<'g, 't, I> {
         Machine {
             table,
             input: input.peekable(),
-            stack: vec![start_symbol],
+            stack: vec![Frame::Symbol(table.start_symbol())],
+            position: 0,
         }
     }
 
-    pub fn step(&mut self) -> bool {
-        match (self.stack.pop(), self.input.peek()) {
-            (None, Some(_symbol)) => {
-                return true;
+    /// The number of tokens consumed so far.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Perform one step of the machine.
+    ///
+    /// Returns `true` once the machine has halted successfully.
+    pub fn step(&mut self, visitor: &mut impl Visitor<'g>) -> Result<bool, ParseError<'g>> {
+        let token = self.input.peek().copied();
+        match self.stack.pop() {
+            None => {
+                if token.is_none() {
+                    Ok(true)
+                } else {
+                    Err(self.unexpected_token([None].into_iter().collect()))
+                }
+            }
+            Some(Frame::Exit(rule)) => {
+                visitor.exit(rule);
+                Ok(false)
             }
-            (Some(state), token) => {
-                if Some(state) == token.copied() {
+            Some(Frame::Symbol(symbol)) if symbol.is_terminal() => {
+                if token == Some(symbol) {
                     self.input.next();
+                    self.position += 1;
+                    visitor.token(symbol);
+                    Ok(false)
                 } else {
-                    let rules = self.table.get(state, token.copied());
-                    match rules.as_slice() {
-                        [] => panic!("Parse error"),
-                        [rule] => {
-                            for symbol in rule.rhs().into_iter().rev() {
-                                self.stack.push(symbol);
-                            }
+                    self.stack.push(Frame::Symbol(symbol));
+                    Err(self.unexpected_token([Some(symbol)].into_iter().collect()))
+                }
+            }
+            Some(Frame::Symbol(symbol)) => {
+                let rules = self.table.get(symbol, token);
+                match rules.as_slice() {
+                    [] => {
+                        self.stack.push(Frame::Symbol(symbol));
+                        Err(self.unexpected_token(self.table.lookaheads(symbol)))
+                    }
+                    [rule] => {
+                        visitor.enter(*rule);
+                        self.stack.push(Frame::Exit(*rule));
+                        for rhs_symbol in rule.rhs().into_iter().rev() {
+                            self.stack.push(Frame::Symbol(rhs_symbol));
                         }
-                        _ => panic!("Conflict"),
+                        Ok(false)
+                    }
+                    _ => {
+                        self.stack.push(Frame::Symbol(symbol));
+                        Err(ParseError::Conflict {
+                            position: self.position,
+                            nonterminal: symbol,
+                            lookahead: token,
+                            rules,
+                        })
                     }
                 }
             }
-            (None, None) => return true,
         }
-        false
     }
 
-    pub fn run(&mut self) {
-        self.dump();
+    /// Run the machine to completion, feeding `visitor` along the way.
+    pub fn run_with(&mut self, visitor: &mut impl Visitor<'g>) -> Result<(), ParseError<'g>> {
+        while !self.step(visitor)? {}
+        Ok(())
+    }
+
+    /// Run the machine to completion and return the parse tree.
+    pub fn run(&mut self) -> Result<ParseTree<'g>, ParseError<'g>> {
+        let mut builder = TreeBuilder::default();
+        self.run_with(&mut builder)?;
+        Ok(builder.finish())
+    }
+
+    /// Run the machine to completion, recovering from any errors along the way.
+    ///
+    /// The visitor is fed whatever structure the machine manages to parse.
+    /// Inserted terminals are passed to `Visitor::token`,
+    /// but nonterminals which are given up on are never entered.
+    /// Returns the diagnostics for every error encountered.
+    pub fn run_with_recovery(&mut self, recovery: &Recovery<'g>, visitor: &mut impl Visitor<'g>) -> Vec<Diagnostic<'g>> {
+        let mut diagnostics = vec![];
         loop {
-            let halt = self.step();
-            self.dump();
-            if halt {
-                break;
+            match self.step(visitor) {
+                Ok(true) => break,
+                Ok(false) => (),
+                Err(error) => {
+                    let repair = self.recover(recovery, &error, visitor);
+                    diagnostics.push(Diagnostic::new(error, repair));
+                }
+            }
+        }
+        diagnostics
+    }
+
+    /// Run the machine to completion, recovering from any errors along the way.
+    ///
+    /// Returns the parse tree if there were no errors,
+    /// and the diagnostics for every error encountered otherwise.
+    pub fn run_recovering(&mut self, recovery: &Recovery<'g>) -> Result<ParseTree<'g>, Vec<Diagnostic<'g>>> {
+        let mut builder = TreeBuilder::default();
+        let diagnostics = self.run_with_recovery(recovery, &mut builder);
+        if diagnostics.is_empty() {
+            Ok(builder.finish())
+        } else {
+            Err(diagnostics)
+        }
+    }
+
+    // Recover from `error`, leaving the machine in a state where it can make progress.
+    // Each recovery either consumes input or changes the top of the stack, so this always terminates.
+    fn recover(&mut self, recovery: &Recovery<'g>, error: &ParseError<'g>, visitor: &mut impl Visitor<'g>) -> Repair<'g> {
+        if let ParseError::Conflict { rules, .. } = error {
+            let rule = rules[0];
+            self.stack.pop();
+            visitor.enter(rule);
+            self.stack.push(Frame::Exit(rule));
+            for rhs_symbol in rule.rhs().into_iter().rev() {
+                self.stack.push(Frame::Symbol(rhs_symbol));
+            }
+            return Repair::Choose(rule);
+        }
+
+        let mut skipped = vec![];
+        match self.stack.last().copied() {
+            // Extra input after the end.
+            None => {
+                skipped.extend(self.input.by_ref());
+                self.position += skipped.len();
+                Repair::Skip { skipped, popped: None }
+            }
+            Some(Frame::Symbol(symbol)) if symbol.is_terminal() => {
+                if recovery.insertion_enabled() {
+                    self.stack.pop();
+                    visitor.token(symbol);
+                    return Repair::Insert(symbol);
+                }
+
+                while let Some(token) = self.input.next_if(|token| *token != symbol) {
+                    skipped.push(token);
+                    self.position += 1;
+                }
+
+                let popped = if self.input.peek().is_none() {
+                    self.stack.pop();
+                    Some(symbol)
+                } else {
+                    None
+                };
+                Repair::Skip { skipped, popped }
+            }
+            Some(Frame::Symbol(nonterminal)) => {
+                loop {
+                    let token = self.input.peek().copied();
+                    if recovery.is_sync(nonterminal, token) || token.is_none() {
+                        self.stack.pop();
+                        return Repair::Skip { skipped, popped: Some(nonterminal) };
+                    }
+
+                    if !skipped.is_empty() && !self.table.get(nonterminal, token).is_empty() {
+                        return Repair::Skip { skipped, popped: None };
+                    }
+
+                    skipped.push(self.input.next().unwrap());
+                    self.position += 1;
+                }
             }
+            Some(Frame::Exit(_rule)) => unreachable!(),
+        }
+    }
+
+    fn unexpected_token(&mut self, expected: BTreeSet<Option<Symbol<'g>>>) -> ParseError<'g> {
+        ParseError::UnexpectedToken {
+            position: self.position,
+            found: self.input.peek().copied(),
+            expected,
+        }
+    }
+}
+
+// A visitor which assembles a `ParseTree`.
+// Each entry of `stack` is a node under construction together with the children seen so far.
+#[derive(Default)]
+struct TreeBuilder<'g> {
+    stack: Vec<(Option<Rule<'g>>, Vec<ParseTree<'g>>)>,
+}
+
+impl<'g> TreeBuilder<'g> {
+    fn finish(mut self) -> ParseTree<'g> {
+        let (_rule, mut children) = self.stack.pop().unwrap();
+        children.pop().unwrap()
+    }
+}
+
+impl<'g> Visitor<'g> for TreeBuilder<'g> {
+    fn enter(&mut self, rule: Rule<'g>) {
+        if self.stack.is_empty() {
+            self.stack.push((None, vec![]));
         }
+        self.stack.push((Some(rule), vec![]));
+    }
+
+    fn exit(&mut self, _rule: Rule<'g>) {
+        let (rule, children) = self.stack.pop().unwrap();
+        let node = ParseTree::Node(rule.unwrap(), children);
+        self.stack.last_mut().unwrap().1.push(node);
     }
 
-    fn dump(&mut self) {
-        eprintln!("Input: {:?}", self.input.peek());
-        eprintln!("Stack: {}", self.stack.iter().map(|symbol| format!("{symbol:?}")).collect::<Vec<_>>().join(" "));
-        eprintln!();
+    fn token(&mut self, symbol: Symbol<'g>) {
+        if self.stack.is_empty() {
+            self.stack.push((None, vec![]));
+        }
+        self.stack.last_mut().unwrap().1.push(ParseTree::Token(symbol));
     }
 }
@@ -0,0 +1,118 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::*;
+use super::*;
+
+/// Configuration for panic-mode error recovery in an LL(1) `Machine`.
+///
+/// When the machine can't make progress with a nonterminal on top of its stack,
+/// it skips tokens until it finds one which the nonterminal can start with
+/// (in which case it resumes parsing the nonterminal)
+/// or one in the nonterminal's synchronizing set
+/// (in which case it gives up on the nonterminal and pops it).
+///
+/// The synchronizing set of a nonterminal is its FOLLOW set
+/// (together with EOF if it can end the input),
+/// and it may be augmented by the user.
+#[derive(Clone)]
+pub struct Recovery<'g> {
+    sync: BTreeMap<Symbol<'g>, BTreeSet<Option<Symbol<'g>>>>,
+    insertion: bool,
+}
+
+/// A diagnostic collected while running a `Machine` with error recovery.
+#[derive(Debug)]
+#[derive(Clone, PartialEq, Eq)]
+pub struct Diagnostic<'g> {
+    error: ParseError<'g>,
+    repair: Repair<'g>,
+}
+
+/// How the machine recovered from an error.
+#[derive(Debug)]
+#[derive(Clone, PartialEq, Eq)]
+pub enum Repair<'g> {
+    /// The terminal on top of the stack was missing from the input,
+    /// and the machine continued as if it had been there.
+    Insert(Symbol<'g>),
+
+    /// The given tokens were skipped.
+    /// Then, if `popped` is set, that symbol was removed from the top of the stack.
+    Skip {
+        skipped: Vec<Symbol<'g>>,
+        popped: Option<Symbol<'g>>,
+    },
+
+    /// The table had a conflict, and the machine picked the first of the rules.
+    Choose(Rule<'g>),
+}
+
+impl<'g> Recovery<'g> {
+    /// Recovery using the FOLLOW sets from `analysis` as the synchronizing sets.
+    ///
+    /// `start_symbol` is the symbol the machine parses,
+    /// and EOF is added to the synchronizing set of any nonterminal which can end it.
+    /// Single-token insertion is enabled.
+    pub fn new(analysis: &GrammarAnalysis<'g>, start_symbol: Symbol<'g>) -> Recovery<'g> {
+        let mut sync = BTreeMap::new();
+        for nonterminal in start_symbol.grammar().nonterminals() {
+            let mut sync_set: BTreeSet<Option<Symbol<'g>>> = analysis.follow(nonterminal).into_iter().map(Some).collect();
+            if analysis.can_end_with(start_symbol, nonterminal) {
+                sync_set.insert(None);
+            }
+            sync.insert(nonterminal, sync_set);
+        }
+
+        Recovery {
+            sync,
+            insertion: true,
+        }
+    }
+
+    /// Add `token` to the synchronizing set of `nonterminal`.
+    pub fn sync(mut self, nonterminal: Symbol<'g>, token: Option<Symbol<'g>>) -> Self {
+        self.sync.entry(nonterminal).or_default().insert(token);
+        self
+    }
+
+    /// Enable or disable single-token insertion.
+    ///
+    /// When disabled, a terminal on top of the stack which doesn't match the input
+    /// is recovered from by skipping tokens until it does.
+    pub fn insertion(mut self, enabled: bool) -> Self {
+        self.insertion = enabled;
+        self
+    }
+
+    /// The synchronizing set for `nonterminal`.
+    pub fn sync_set(&self, nonterminal: Symbol<'g>) -> BTreeSet<Option<Symbol<'g>>> {
+        self.sync.get(&nonterminal).cloned().unwrap_or_default()
+    }
+
+    pub(crate) fn is_sync(&self, nonterminal: Symbol<'g>, token: Option<Symbol<'g>>) -> bool {
+        self.sync.get(&nonterminal).is_some_and(|sync_set| sync_set.contains(&token))
+    }
+
+    pub(crate) fn insertion_enabled(&self) -> bool {
+        self.insertion
+    }
+}
+
+impl<'g> Diagnostic<'g> {
+    pub(crate) fn new(error: ParseError<'g>, repair: Repair<'g>) -> Diagnostic<'g> {
+        Diagnostic {
+            error,
+            repair,
+        }
+    }
+
+    /// The error which was encountered.
+    pub fn error(&self) -> &ParseError<'g> {
+        &self.error
+    }
+
+    /// How the machine recovered from the error.
+    pub fn repair(&self) -> &Repair<'g> {
+        &self.repair
+    }
+}
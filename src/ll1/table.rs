@@ -1,19 +1,24 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 
 use crate::*;
+use super::*;
 
 pub struct ParseTable<'g> {
     grammar: &'g Grammar,
     start_symbol: Symbol<'g>,
+    analysis: GrammarAnalysis<'g>,
     table: BTreeMap<(Symbol<'g>, Option<Symbol<'g>>), Vec<Rule<'g>>>,
 }
 
 impl<'g> ParseTable<'g> {
     pub fn build(grammar: &'g Grammar, start_symbol: Symbol<'g>) -> ParseTable<'g> {
+        let analysis = GrammarAnalysis::build(grammar);
+        let table = Self::build_table(grammar, &analysis, start_symbol);
         ParseTable {
             grammar,
             start_symbol,
-            table: Self::build_table(grammar, start_symbol),
+            analysis,
+            table,
         }
     }
 
@@ -25,49 +30,69 @@ impl<'g> ParseTable<'g> {
         self.start_symbol
     }
 
-    fn build_table(grammar: &'g Grammar, start_symbol: Symbol<'g>)
+    /// The analysis of the grammar used to build this table.
+    pub fn analysis(&self) -> &GrammarAnalysis<'g> {
+        &self.analysis
+    }
+
+    fn build_table(grammar: &'g Grammar, analysis: &GrammarAnalysis<'g>, start_symbol: Symbol<'g>)
         -> BTreeMap<(Symbol<'g>, Option<Symbol<'g>>), Vec<Rule<'g>>> {
 
-        let analysis = GrammarAnalysis::build(grammar);
-        let mut map = BTreeMap::new();
-
-        let rules = grammar.rules();
-
-        for rule in rules.iter().copied() {
-            let rhs = rule.rhs();
-            if analysis.is_nullable_seq(&rhs) {
-                for follow in analysis.follow(rule.lhs()) {
-                    let key = (rule.lhs(), Some(follow));
-                    if !map.contains_key(&key) {
-                        map.insert(key, vec![]);
-                    }
-                    map.get_mut(&key).unwrap().push(rule);
-                }
+        let mut map: BTreeMap<_, Vec<Rule<'g>>> = BTreeMap::new();
 
-                if analysis.can_end_with(start_symbol, rule.lhs()) {
-                    let key = (rule.lhs(), None);
-                    if !map.contains_key(&key) {
-                        map.insert(key, vec![]);
-                    }
-                    map.get_mut(&key).unwrap().push(rule);
-                }
-            } else {
-                for first in analysis.first_seq(&rhs) {
-                    let key = (rule.lhs(), Some(first));
-                    if !map.contains_key(&key) {
-                        map.insert(key, vec![]);
-                    }
-                    map.get_mut(&key).unwrap().push(rule);
-                }
+        for rule in grammar.rules() {
+            for lookahead in Self::predictions(analysis, start_symbol, rule) {
+                map.entry((rule.lhs(), lookahead)).or_default().push(rule);
             }
         }
 
         map
     }
 
+    // The lookaheads on which `rule` is predicted.
+    // These are FIRST of the RHS, together with FOLLOW of the LHS when the RHS is nullable.
+    fn predictions(analysis: &GrammarAnalysis<'g>, start_symbol: Symbol<'g>, rule: Rule<'g>) -> BTreeSet<Option<Symbol<'g>>> {
+        let rhs = rule.rhs();
+        let mut lookaheads: BTreeSet<Option<Symbol<'g>>> = analysis.first_seq(&rhs).into_iter().map(Some).collect();
+
+        if analysis.is_nullable_seq(&rhs) {
+            lookaheads.extend(analysis.follow(rule.lhs()).into_iter().map(Some));
+
+            if analysis.can_end_with(start_symbol, rule.lhs()) {
+                lookaheads.insert(None);
+            }
+        }
+
+        lookaheads
+    }
+
+    /// Return a list of all of the conflicts found in this table.
+    ///
+    /// A conflict is reported for each pair of rules which are predicted in the same cell.
+    pub fn conflicts(&self) -> Vec<Conflict<'g>> {
+        let mut conflicts = vec![];
+        for ((nonterminal, lookahead), rules) in &self.table {
+            for (i, rule1) in rules.iter().enumerate() {
+                for rule2 in &rules[i + 1..] {
+                    conflicts.push(Conflict::new(self, *nonterminal, *lookahead, *rule1, *rule2));
+                }
+            }
+        }
+        conflicts
+    }
+
     pub fn get(&self, state: Symbol<'g>, input: Option<Symbol<'g>>) -> Vec<Rule<'g>> {
         self.table.get(&(state, input)).map(|v| v.as_slice()).unwrap_or_else(|| &[]).to_vec()
     }
+
+    /// The set of lookaheads for which some rule of `nonterminal` is predicted.
+    pub fn lookaheads(&self, nonterminal: Symbol<'g>) -> BTreeSet<Option<Symbol<'g>>> {
+        self.table
+            .keys()
+            .filter(|(symbol, _lookahead)| *symbol == nonterminal)
+            .map(|(_symbol, lookahead)| *lookahead)
+            .collect()
+    }
 }
 
 impl<'g> std::fmt::Debug for ParseTable<'g> {
@@ -0,0 +1,5 @@
+mod table;
+mod adaptive;
+
+pub use table::{ParseTable, Conflict};
+pub use adaptive::{AdaptiveTable, Decision, Unresolved, UnresolvedReason};
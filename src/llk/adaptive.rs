@@ -0,0 +1,366 @@
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+use crate::*;
+use crate::dfa::{Dfa, StateIdx, SymbolIdx};
+
+/// An LL(*)-style prediction table.
+///
+/// For each nonterminal with more than one rule, a lookahead DFA decides between the rules.
+/// The DFA may look arbitrarily far ahead, as long as the lookahead language is regular.
+///
+/// The DFA is built by simulating every rule in parallel, one token at a time,
+/// until only one rule remains viable.
+/// Once a rule has been fully matched, parsing continues in every context in which its LHS appears,
+/// so the contexts are approximated in the same way as FOLLOW sets (ANTLR calls this SLL).
+pub struct AdaptiveTable<'g> {
+    grammar: &'g Grammar,
+    decisions: BTreeMap<Symbol<'g>, Decision<'g>>,
+    unresolved: Vec<Unresolved<'g>>,
+}
+
+/// The lookahead DFA for a single nonterminal.
+///
+/// The DFA reads symbols by their `SymbolIndex`.
+/// The column one past the last symbol represents EOF.
+pub struct Decision<'g> {
+    nonterminal: Symbol<'g>,
+    dfa: Dfa,
+    predictions: BTreeMap<StateIdx, Rule<'g>>,
+    dead_state: StateIdx,
+}
+
+/// A nonterminal for which no lookahead DFA could be built.
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct Unresolved<'g> {
+    nonterminal: Symbol<'g>,
+    lookahead: Vec<Option<Symbol<'g>>>,
+    rules: Vec<Rule<'g>>,
+    reason: UnresolvedReason,
+}
+
+/// Why a lookahead DFA couldn't be built.
+#[derive(Debug)]
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum UnresolvedReason {
+    /// After the lookahead, the rules are in exactly the same position,
+    /// so no amount of further lookahead can decide between them.
+    Ambiguous,
+
+    /// Deciding requires expanding a left-recursive nonterminal.
+    LeftRecursive,
+
+    /// The DFA grew past the limit on the number of states.
+    /// This happens when the lookahead language isn't regular.
+    TooManyStates,
+}
+
+// A rule being simulated, together with the symbols it has yet to match.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct Config<'g> {
+    // The rule for the decision's nonterminal which this configuration predicts.
+    rule: Rule<'g>,
+    // The symbols left to match, with the next one at the end.
+    stack: Vec<Symbol<'g>>,
+    // The nonterminal which is complete once the stack is empty.
+    // `None` when the end of the input is all that's left.
+    context: Option<Symbol<'g>>,
+}
+
+impl<'g> AdaptiveTable<'g> {
+    /// Build the lookahead DFAs for `grammar`.
+    ///
+    /// A decision is given up on if its DFA would need more than `max_states` states.
+    pub fn build(grammar: &'g Grammar, max_states: usize) -> AdaptiveTable<'g> {
+        let mut decisions = BTreeMap::new();
+        let mut unresolved = vec![];
+
+        for nonterminal in grammar.nonterminals() {
+            if nonterminal.rules().len() < 2 {
+                continue;
+            }
+
+            match Decision::build(nonterminal, max_states) {
+                Ok(decision) => {
+                    decisions.insert(nonterminal, decision);
+                }
+                Err(error) => unresolved.push(error),
+            }
+        }
+
+        AdaptiveTable {
+            grammar,
+            decisions,
+            unresolved,
+        }
+    }
+
+    pub fn grammar(&self) -> &'g Grammar {
+        self.grammar
+    }
+
+    /// The lookahead DFA for `nonterminal`.
+    ///
+    /// Returns `None` for nonterminals with a single rule, and for those which are unresolved.
+    pub fn decision(&self, nonterminal: Symbol<'g>) -> Option<&Decision<'g>> {
+        self.decisions.get(&nonterminal)
+    }
+
+    /// The decisions for which no lookahead DFA could be built.
+    pub fn unresolved(&self) -> &[Unresolved<'g>] {
+        &self.unresolved
+    }
+
+    /// Predict which rule to use for `nonterminal` given the upcoming input.
+    ///
+    /// The input should end with `None` if it reaches EOF.
+    /// Returns `None` if the input can't begin any of the rules,
+    /// if it ran out before a decision was reached,
+    /// or if the decision is unresolved.
+    pub fn predict(&self, nonterminal: Symbol<'g>, input: impl IntoIterator<Item=Option<Symbol<'g>>>) -> Option<Rule<'g>> {
+        let rules = nonterminal.rules();
+        if rules.len() == 1 {
+            return Some(rules[0]);
+        }
+        self.decision(nonterminal)?.predict(input)
+    }
+}
+
+impl<'g> Decision<'g> {
+    fn build(nonterminal: Symbol<'g>, max_states: usize) -> Result<Decision<'g>, Unresolved<'g>> {
+        let grammar = nonterminal.grammar();
+        let eof = grammar.symbols().len();
+
+        let seed = nonterminal
+            .rules()
+            .into_iter()
+            .map(|rule| Config {
+                rule,
+                stack: rule.rhs().into_iter().rev().collect(),
+                context: Some(nonterminal),
+            })
+            .collect();
+
+        let unresolved = |lookahead: &[Option<Symbol<'g>>], rules: Vec<Rule<'g>>, reason| Unresolved {
+            nonterminal,
+            lookahead: lookahead.to_vec(),
+            rules,
+            reason,
+        };
+
+        let start_state = closure(seed).map_err(|_| unresolved(&[], nonterminal.rules(), UnresolvedReason::LeftRecursive))?;
+
+        // The configurations in each state, and the shortest lookahead which reaches it.
+        let mut states: Vec<(BTreeSet<Config<'g>>, Vec<Option<Symbol<'g>>>)> = vec![(start_state, vec![])];
+        let mut transitions: Vec<BTreeMap<SymbolIdx, StateIdx>> = vec![BTreeMap::new()];
+        let mut predictions = BTreeMap::new();
+        let mut queue = VecDeque::from([0]);
+
+        while let Some(state) = queue.pop_front() {
+            let (configs, lookahead) = states[state].clone();
+
+            let rules: BTreeSet<Rule<'g>> = configs.iter().map(|config| config.rule).collect();
+            if rules.len() == 1 {
+                predictions.insert(state, *rules.first().unwrap());
+                continue;
+            }
+
+            // If two rules are in the same position, they can never be told apart.
+            let mut positions: BTreeMap<(&[Symbol<'g>], Option<Symbol<'g>>), Vec<Rule<'g>>> = BTreeMap::new();
+            for config in &configs {
+                positions.entry((&config.stack, config.context)).or_default().push(config.rule);
+            }
+            if let Some(rules) = positions.into_values().find(|rules| rules.len() > 1) {
+                return Err(unresolved(&lookahead, rules, UnresolvedReason::Ambiguous));
+            }
+
+            let mut moves: BTreeMap<Option<Symbol<'g>>, Vec<Config<'g>>> = BTreeMap::new();
+            for config in &configs {
+                let mut config = config.clone();
+                let symbol = config.stack.pop();
+                moves.entry(symbol).or_default().push(config);
+            }
+
+            for (symbol, seed) in moves {
+                let mut next_lookahead = lookahead.clone();
+                next_lookahead.push(symbol);
+
+                // Configurations which are waiting on EOF have nothing left to simulate.
+                let next_configs = match symbol {
+                    Some(_terminal) => closure(seed).map_err(|_| {
+                        unresolved(&next_lookahead, nonterminal.rules(), UnresolvedReason::LeftRecursive)
+                    })?,
+                    None => seed.into_iter().collect(),
+                };
+
+                let next_state = match states.iter().position(|(configs, _lookahead)| *configs == next_configs) {
+                    Some(next_state) => next_state,
+                    None => {
+                        if states.len() == max_states {
+                            return Err(unresolved(&next_lookahead, nonterminal.rules(), UnresolvedReason::TooManyStates));
+                        }
+                        states.push((next_configs, next_lookahead));
+                        transitions.push(BTreeMap::new());
+                        queue.push_back(states.len() - 1);
+                        states.len() - 1
+                    }
+                };
+
+                let column = symbol.map(|symbol| usize::from(symbol.index())).unwrap_or(eof);
+                transitions[state].insert(column, next_state);
+            }
+        }
+
+        // Every missing transition goes to a dead state at the end.
+        let dead_state = states.len();
+        let mut rows: Vec<Vec<StateIdx>> = transitions
+            .into_iter()
+            .map(|row| (0..=eof).map(|column| row.get(&column).copied().unwrap_or(dead_state)).collect())
+            .collect();
+        rows.push(vec![dead_state; eof + 1]);
+
+        Ok(Decision {
+            nonterminal,
+            dfa: Dfa::new(rows),
+            predictions,
+            dead_state,
+        })
+    }
+
+    pub fn nonterminal(&self) -> Symbol<'g> {
+        self.nonterminal
+    }
+
+    /// The lookahead DFA.
+    pub fn dfa(&self) -> &Dfa {
+        &self.dfa
+    }
+
+    /// The states of the DFA in which a rule has been decided on.
+    pub fn predictions(&self) -> &BTreeMap<StateIdx, Rule<'g>> {
+        &self.predictions
+    }
+
+    /// Run the DFA on the upcoming input until it decides on a rule.
+    ///
+    /// The input should end with `None` if it reaches EOF.
+    /// Returns `None` if the input can't begin any of the rules,
+    /// or if it ran out before a decision was reached.
+    pub fn predict(&self, input: impl IntoIterator<Item=Option<Symbol<'g>>>) -> Option<Rule<'g>> {
+        let eof = self.dfa.num_symbols() - 1;
+        let mut state = 0;
+        let mut input = input.into_iter();
+
+        loop {
+            if let Some(rule) = self.predictions.get(&state) {
+                return Some(*rule);
+            }
+
+            let column = match input.next()? {
+                Some(symbol) => usize::from(symbol.index()),
+                None => eof,
+            };
+            state = self.dfa.transition(state, column);
+
+            if state == self.dead_state {
+                return None;
+            }
+        }
+    }
+}
+
+impl<'g> Unresolved<'g> {
+    pub fn nonterminal(&self) -> Symbol<'g> {
+        self.nonterminal
+    }
+
+    /// The lookahead after which the decision gets stuck.
+    pub fn lookahead(&self) -> &[Option<Symbol<'g>>] {
+        &self.lookahead
+    }
+
+    /// The rules which couldn't be decided between.
+    pub fn rules(&self) -> &[Rule<'g>] {
+        &self.rules
+    }
+
+    pub fn reason(&self) -> UnresolvedReason {
+        self.reason
+    }
+}
+
+// Expand the configurations until each one is either waiting on a terminal or waiting on EOF.
+// Fails if a left-recursive nonterminal is expanded.
+fn closure<'g>(seed: Vec<Config<'g>>) -> Result<BTreeSet<Config<'g>>, ()> {
+    let mut visited = BTreeSet::new();
+    let mut result = BTreeSet::new();
+    for config in seed {
+        close(config, &mut vec![], &mut visited, &mut result)?;
+    }
+    Ok(result)
+}
+
+// `expanding` holds the configurations on the current path which expanded a nonterminal.
+// If we come back to the same nonterminal with the same context,
+// and everything underneath it on the stack is unchanged,
+// then the stack will grow without bound.
+fn close<'g>(
+    config: Config<'g>,
+    expanding: &mut Vec<Config<'g>>,
+    visited: &mut BTreeSet<Config<'g>>,
+    result: &mut BTreeSet<Config<'g>>,
+) -> Result<(), ()> {
+    if !visited.insert(config.clone()) {
+        return Ok(());
+    }
+
+    match config.stack.last().copied() {
+        Some(symbol) if symbol.is_terminal() => {
+            result.insert(config);
+        }
+        Some(nonterminal) => {
+            let left_recursive = expanding.iter().any(|earlier| {
+                earlier.context == config.context &&
+                    earlier.stack.last() == Some(&nonterminal) &&
+                    earlier.stack.len() < config.stack.len() &&
+                    config.stack.starts_with(&earlier.stack[..earlier.stack.len() - 1])
+            });
+            if left_recursive {
+                return Err(());
+            }
+
+            expanding.push(config.clone());
+            let mut below = config.stack.clone();
+            below.pop();
+            for rule in nonterminal.rules() {
+                let mut stack = below.clone();
+                stack.extend(rule.rhs().into_iter().rev());
+                close(Config { stack, ..config.clone() }, expanding, visited, result)?;
+            }
+            expanding.pop();
+        }
+        None => match config.context {
+            None => {
+                result.insert(config);
+            }
+            Some(context) => {
+                let grammar = context.grammar();
+                if context == grammar.start_symbol() {
+                    close(Config { context: None, ..config.clone() }, expanding, visited, result)?;
+                }
+
+                for rule in grammar.rules() {
+                    let rhs = rule.rhs();
+                    for (i, symbol) in rhs.iter().enumerate() {
+                        if *symbol == context {
+                            let stack = rhs[i + 1..].iter().rev().copied().collect();
+                            let next = Config { stack, context: Some(rule.lhs()), ..config.clone() };
+                            close(next, expanding, visited, result)?;
+                        }
+                    }
+                }
+            }
+        },
+    }
+    Ok(())
+}
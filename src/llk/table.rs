@@ -0,0 +1,117 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::*;
+use crate::analysis::concat_k;
+
+/// A strong LL(k) parse table.
+///
+/// Each cell is keyed by a nonterminal and a lookahead string of up to `k` symbols.
+/// A lookahead shorter than `k` always ends in `None`, which represents EOF.
+pub struct ParseTable<'g> {
+    grammar: &'g Grammar,
+    k: usize,
+    table: BTreeMap<(Symbol<'g>, Vec<Option<Symbol<'g>>>), Vec<Rule<'g>>>,
+}
+
+/// A cell of an LL(k) parse table in which more than one rule is predicted.
+#[derive(Debug)]
+#[derive(Clone)]
+pub struct Conflict<'g> {
+    nonterminal: Symbol<'g>,
+    lookahead: Vec<Option<Symbol<'g>>>,
+    rules: Vec<Rule<'g>>,
+}
+
+impl<'g> ParseTable<'g> {
+    /// Build a parse table for the grammar's start symbol using `k` symbols of lookahead.
+    ///
+    /// A rule `A -> α` is predicted on every string in FIRST_k(α) followed by FOLLOW_k(A).
+    pub fn build(grammar: &'g Grammar, k: usize) -> ParseTable<'g> {
+        let analysis = GrammarAnalysis::build_k(grammar, k);
+        let mut table: BTreeMap<_, Vec<Rule<'g>>> = BTreeMap::new();
+
+        for rule in grammar.rules() {
+            let firsts: BTreeSet<Vec<Option<Symbol<'g>>>> = analysis
+                .first_k(&rule.rhs())
+                .into_iter()
+                .map(|string| string.into_iter().map(Some).collect())
+                .collect();
+
+            for lookahead in concat_k(&firsts, &analysis.follow_k(rule.lhs()), k) {
+                table.entry((rule.lhs(), lookahead)).or_default().push(rule);
+            }
+        }
+
+        ParseTable {
+            grammar,
+            k,
+            table,
+        }
+    }
+
+    pub fn grammar(&self) -> &'g Grammar {
+        self.grammar
+    }
+
+    /// The number of symbols of lookahead used by this table.
+    pub fn k(&self) -> usize {
+        self.k
+    }
+
+    /// The rules predicted for `nonterminal` when `lookahead` is next in the input.
+    pub fn get(&self, nonterminal: Symbol<'g>, lookahead: &[Option<Symbol<'g>>]) -> Vec<Rule<'g>> {
+        let key = (nonterminal, lookahead.to_vec());
+        self.table.get(&key).cloned().unwrap_or_default()
+    }
+
+    /// The set of lookahead strings for which some rule of `nonterminal` is predicted.
+    pub fn lookaheads(&self, nonterminal: Symbol<'g>) -> BTreeSet<Vec<Option<Symbol<'g>>>> {
+        self.table
+            .keys()
+            .filter(|(symbol, _lookahead)| *symbol == nonterminal)
+            .map(|(_symbol, lookahead)| lookahead.clone())
+            .collect()
+    }
+
+    /// Return a list of all of the conflicts found in this table.
+    pub fn conflicts(&self) -> Vec<Conflict<'g>> {
+        let mut conflicts = vec![];
+        for ((nonterminal, lookahead), rules) in &self.table {
+            if rules.len() > 1 {
+                conflicts.push(Conflict {
+                    nonterminal: *nonterminal,
+                    lookahead: lookahead.clone(),
+                    rules: rules.clone(),
+                });
+            }
+        }
+        conflicts
+    }
+}
+
+impl<'g> std::fmt::Debug for ParseTable<'g> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for nonterminal in self.grammar.nonterminals() {
+            writeln!(f, "{nonterminal:?}")?;
+            for lookahead in self.lookaheads(nonterminal) {
+                writeln!(f, "    {lookahead:?}\t{:?}", self.get(nonterminal, &lookahead))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'g> Conflict<'g> {
+    pub fn nonterminal(&self) -> Symbol<'g> {
+        self.nonterminal
+    }
+
+    pub fn lookahead(&self) -> &[Option<Symbol<'g>>] {
+        &self.lookahead
+    }
+
+    /// The competing rules.
+    pub fn rules(&self) -> &[Rule<'g>] {
+        &self.rules
+    }
+}
@@ -144,14 +144,7 @@ impl<'g> ItemSet<'g> {
                         if !nonterms_added.contains(&symbol) {
                             nonterms_added.insert(symbol);
 
-                            let symbol_rules = self.grammar
-                                .rules()
-                                .into_iter()
-                                .filter(|rule| {
-                                    rule.lhs() == symbol
-                                });
-
-                            for rule in symbol_rules {
+                            for rule in symbol.rules() {
                                 let item = Item::new(rule, 0);
                                 new_items.push(item);
                                 dirty = true;
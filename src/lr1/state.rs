@@ -107,14 +107,7 @@ impl<'g> State<'g> {
                     // Adding this rule may enable even more items in the next iteration.
                     // Set `dirty` to `true` to indicate we need to iterate again.
                     if next_symbol.is_nonterminal() {
-                        let symbol_rules = self.grammar
-                            .rules()
-                            .into_iter()
-                            .filter(|rule| {
-                                rule.lhs() == next_symbol
-                            });
-
-                        for rule in symbol_rules {
+                        for rule in next_symbol.rules() {
                             let item = Item::new(rule, 0, lookahead.clone());
                             if !itemset.contains(&item) {
                                 new_items.push(item);
@@ -0,0 +1,9 @@
+mod table;
+mod machine;
+mod state;
+mod item;
+
+pub use state::{State, StateIndex};
+pub use table::{ParseTable, Action, Conflict, minimal_k};
+pub use machine::{Machine, ParseError};
+pub use item::Item;
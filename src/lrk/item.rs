@@ -0,0 +1,99 @@
+use std::collections::BTreeSet;
+
+use crate::*;
+
+/// An LR(k) item.
+///
+/// Like an LR(1) item, except that each lookahead is a string of up to `k` symbols.
+/// A lookahead shorter than `k` always ends in `None`, which represents EOF.
+#[derive(Clone)]
+pub struct Item<'g> {
+    rule: Rule<'g>,
+    pos: usize,
+    lookahead: BTreeSet<Vec<Option<Symbol<'g>>>>,
+}
+
+impl<'g> Item<'g> {
+    pub fn new(rule: Rule<'g>, pos: usize, lookahead: BTreeSet<Vec<Option<Symbol<'g>>>>) -> Item<'g> {
+        assert!(pos <= rule.rhs().len());
+
+        Item {
+            rule,
+            pos,
+            lookahead,
+        }
+    }
+
+    pub fn rule(&self) -> Rule<'g> {
+        self.rule
+    }
+
+    pub fn pos(&self) -> usize {
+        self.pos
+    }
+
+    pub fn lookahead(&self) -> &BTreeSet<Vec<Option<Symbol<'g>>>> {
+        &self.lookahead
+    }
+
+    pub fn grammar(&self) -> &'g Grammar {
+        self.rule.grammar()
+    }
+
+    pub fn rhs(&self) -> Vec<Symbol<'g>> {
+        self.rule.rhs()
+    }
+
+    pub fn next_symbol(&self) -> Option<Symbol<'g>> {
+        self.rhs().get(self.pos).copied()
+    }
+
+    /// The symbols after the one at the cursor.
+    pub fn rest(&self) -> Vec<Symbol<'g>> {
+        self.rhs().into_iter().skip(self.pos + 1).collect()
+    }
+
+    pub fn step(&self) -> Option<Item<'g>> {
+        if self.pos() < self.rhs().len() {
+            Some(Item::new(self.rule, self.pos + 1, self.lookahead.clone()))
+        } else {
+            None
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.pos() == self.rhs().len()
+    }
+}
+
+impl<'g> std::fmt::Debug for Item<'g> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let lhs = self.rule.lhs();
+        let rhs = self.rule.rhs();
+        write!(f, "{lhs:?} ->")?;
+        for symbol in &rhs[..self.pos] {
+            write!(f, " {symbol:?}")?;
+        }
+
+        write!(f, " .")?;
+
+        for symbol in &rhs[self.pos..] {
+            write!(f, " {symbol:?}")?;
+        }
+
+        write!(f, " {{ ")?;
+        for string in &self.lookahead {
+            write!(f, "{string:?} ")?;
+        }
+        write!(f, "}}")?;
+        Ok(())
+    }
+}
+
+impl<'g> PartialEq for Item<'g> {
+    fn eq(&self, other: &Self) -> bool {
+        self.rule() == other.rule() && self.pos == other.pos && self.lookahead == other.lookahead
+    }
+}
+
+impl<'g> Eq for Item<'g> {}
@@ -0,0 +1,140 @@
+use std::collections::VecDeque;
+
+use crate::*;
+use super::*;
+
+/// A machine which runs an LR(k) parse table against an input stream.
+///
+/// The machine keeps up to `k` symbols of the input buffered so it can peek at them.
+pub struct Machine<'g, 't, I>
+where I: Iterator<Item=Symbol<'g>> {
+    input: I,
+    buffer: VecDeque<Symbol<'g>>,
+    parse_table: &'t ParseTable<'g>,
+    stack: Vec<(StateIndex, Symbol<'g>)>,
+    position: usize,
+}
+
+/// An error encountered while running an LR(k) machine.
+#[derive(Debug)]
+#[derive(Clone, PartialEq, Eq)]
+pub enum ParseError<'g> {
+    /// The table has no action for the lookahead.
+    ///
+    /// The lookahead ends with `None` when it reaches the end of the input.
+    UnexpectedToken {
+        position: usize,
+        lookahead: Vec<Option<Symbol<'g>>>,
+    },
+
+    /// The table has more than one action for the lookahead.
+    Conflict {
+        position: usize,
+        lookahead: Vec<Option<Symbol<'g>>>,
+        actions: Vec<Action<'g>>,
+    },
+}
+
+impl<'g> ParseError<'g> {
+    /// The number of tokens consumed before the error.
+    pub fn position(&self) -> usize {
+        match self {
+            ParseError::UnexpectedToken { position, .. } => *position,
+            ParseError::Conflict { position, .. } => *position,
+        }
+    }
+}
+
+impl<'g, 't, I> Machine<'g, 't, I>
+where I: Iterator<Item=Symbol<'g>> {
+    pub fn new(parse_table: &'t ParseTable<'g>, input: I) -> Machine<'g, 't, I> {
+        Machine {
+            input,
+            buffer: VecDeque::new(),
+            parse_table,
+            stack: vec![],
+            position: 0,
+        }
+    }
+
+    /// The number of tokens consumed so far.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    fn state(&self) -> StateIndex {
+        self.stack
+            .last()
+            .map(|(state_index, _symbol)| {
+                *state_index
+            })
+            .unwrap_or(StateIndex(0))
+    }
+
+    // Peek at the next `k` symbols of the input.
+    // If the input runs out first, the lookahead ends with `None`.
+    fn lookahead(&mut self) -> Vec<Option<Symbol<'g>>> {
+        while self.buffer.len() < self.parse_table.k() {
+            match self.input.next() {
+                Some(symbol) => self.buffer.push_back(symbol),
+                None => break,
+            }
+        }
+
+        let mut lookahead: Vec<Option<Symbol<'g>>> = self.buffer.iter().copied().map(Some).collect();
+        if lookahead.len() < self.parse_table.k() {
+            lookahead.push(None);
+        }
+        lookahead
+    }
+
+    // Perform one step of the machine.
+    // Returns `true` once the machine has accepted the input.
+    fn step(&mut self) -> Result<bool, ParseError<'g>> {
+        let lookahead = self.lookahead();
+        let state = self.state();
+        let actions = self.parse_table.get(state, &lookahead);
+
+        let action = match actions.as_slice() {
+            [] => return Err(ParseError::UnexpectedToken {
+                position: self.position,
+                lookahead,
+            }),
+            [action] => *action,
+            _ => return Err(ParseError::Conflict {
+                position: self.position,
+                lookahead,
+                actions,
+            }),
+        };
+
+        match action {
+            Action::Shift(dst_state_index) => {
+                let symbol = self.buffer.pop_front().unwrap();
+                self.stack.push((dst_state_index, symbol));
+                self.position += 1;
+            }
+            Action::Reduce(rule) => {
+                for _ in 0..rule.rhs().len() {
+                    self.stack.pop().unwrap();
+                }
+
+                if rule == self.parse_table.grammar().start_rule() {
+                    return Ok(true);
+                }
+
+                match self.parse_table.goto(self.state(), rule.lhs()) {
+                    Some(dst_state_index) => self.stack.push((dst_state_index, rule.lhs())),
+                    None => panic!("Expected GOTO after reducing {rule:?}"),
+                }
+            }
+        }
+        Ok(false)
+    }
+
+    /// Run the machine until it accepts the input or halts with an error.
+    pub fn run(&mut self) -> Result<(), ParseError<'g>> {
+        while !self.step()? {}
+        Ok(())
+    }
+}
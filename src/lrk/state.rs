@@ -0,0 +1,152 @@
+use std::collections::{BTreeSet, BTreeMap};
+
+use crate::*;
+use crate::analysis::concat_k;
+use super::*;
+
+/// An LR(k) state.
+/// Consists of an LR(k) itemset.
+#[derive(Clone)]
+pub struct State<'g> {
+    grammar: &'g Grammar,
+    items: Vec<Item<'g>>,
+}
+
+/// The index of a given state.
+#[derive(Debug)]
+#[derive(Clone, Copy, Eq, PartialEq, Hash, Ord, PartialOrd)]
+pub struct StateIndex(pub(crate) usize);
+
+impl From<StateIndex> for usize {
+    fn from(value: StateIndex) -> Self {
+        value.0
+    }
+}
+
+impl<'g> std::fmt::Debug for State<'g> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for item in self.items() {
+            writeln!(f, "{item:?}")?;
+        }
+        Ok(())
+    }
+}
+
+impl<'g> PartialEq for State<'g> {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self.grammar, other.grammar) && self.items == other.items
+    }
+}
+
+impl<'g> Eq for State<'g> {}
+
+impl<'g> State<'g> {
+    /// Get the underlying `Grammar` for this state.
+    pub fn grammar(&self) -> &'g Grammar {
+        self.grammar
+    }
+
+    /// Get the itemset for this state.
+    pub fn items(&self) -> &[Item<'g>] {
+        self.items.as_slice()
+    }
+
+    /// Generates the state representing the closure of a single item.
+    pub(crate) fn singleton(item: Item<'g>, analysis: &GrammarAnalysis<'g>) -> Self {
+        let grammar: &'g Grammar = item.grammar();
+        let itemset = State {
+            grammar,
+            items: vec![item],
+        };
+        itemset.closure(analysis)
+    }
+
+    /// Calculate the ε-closure of the items in this state.
+    ///
+    /// This works as it does for LR(1), except that the lookahead for a new item
+    /// is FIRST_k of everything following the nonterminal at the cursor,
+    /// extended by the lookahead of the item which predicted it.
+    pub(crate) fn closure(&self, analysis: &GrammarAnalysis<'g>) -> State<'g> {
+        let mut itemset = self.items.clone();
+
+        // Iterate repeatedly until no new items are found.
+        loop {
+            let mut new_items = vec![];
+
+            for item in &itemset {
+                if let Some(next_symbol) = item.next_symbol() && next_symbol.is_nonterminal() {
+                    let lookahead = Self::lookahead_after(analysis, &item.rest(), item.lookahead());
+
+                    for rule in next_symbol.rules() {
+                        let item = Item::new(rule, 0, lookahead.clone());
+                        if !itemset.contains(&item) && !new_items.contains(&item) {
+                            new_items.push(item);
+                        }
+                    }
+                }
+            }
+
+            // And if we iterated without changing anything,
+            // then we're done.
+            if new_items.is_empty() {
+                break;
+            }
+            itemset.extend(new_items);
+        }
+
+        State {
+            grammar: self.grammar,
+            items: Self::squash(itemset),
+        }
+    }
+
+    // The lookahead strings for the position just before `seq`,
+    // given `lookahead` follows `seq`.
+    pub(crate) fn lookahead_after(
+        analysis: &GrammarAnalysis<'g>,
+        seq: &[Symbol<'g>],
+        lookahead: &BTreeSet<Vec<Option<Symbol<'g>>>>,
+    ) -> BTreeSet<Vec<Option<Symbol<'g>>>> {
+        let firsts: BTreeSet<Vec<Option<Symbol<'g>>>> = analysis
+            .first_k(seq)
+            .into_iter()
+            .map(|string| string.into_iter().map(Some).collect())
+            .collect();
+        concat_k(&firsts, lookahead, analysis.k())
+    }
+
+    // Merge the lookaheads of items which only differ by lookahead.
+    fn squash(itemset: Vec<Item<'g>>) -> Vec<Item<'g>> {
+        let mut lookaheads: BTreeMap<(Rule<'g>, usize), BTreeSet<Vec<Option<Symbol<'g>>>>> = BTreeMap::new();
+        for item in itemset {
+            lookaheads
+                .entry((item.rule(), item.pos()))
+                .or_default()
+                .extend(item.lookahead().iter().cloned());
+        }
+        lookaheads
+            .into_iter()
+            .map(|((rule, pos), lookaheads)| {
+                Item::new(rule, pos, lookaheads)
+            })
+            .collect()
+    }
+
+    /// Take the current state and calculate which state is reached
+    /// when it shifts `symbol` onto the stack.
+    pub fn follow(&self, analysis: &GrammarAnalysis<'g>, symbol: Symbol<'g>) -> State<'g> {
+        let mut items = vec![];
+        for item in &self.items {
+            if item.next_symbol() == Some(symbol) {
+                items.push(item.step().unwrap());
+            }
+        }
+
+        let itemset = State {
+            grammar: self.grammar,
+            items,
+        };
+
+        itemset.closure(analysis)
+    }
+}
@@ -0,0 +1,262 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::*;
+use super::*;
+
+/// A canonical LR(k) parse table.
+#[derive(Debug)]
+pub struct ParseTable<'g> {
+    grammar: &'g Grammar,
+    k: usize,
+    states: Vec<State<'g>>,
+    actions: BTreeMap<(StateIndex, Vec<Option<Symbol<'g>>>), Vec<Action<'g>>>,
+    gotos: BTreeMap<(StateIndex, Symbol<'g>), StateIndex>,
+}
+
+/// An LR(k) action.
+#[derive(Debug)]
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Action<'g> {
+    /// Shift the next symbol from the input stream onto the stack.
+    /// Then enter the given state.
+    Shift(StateIndex),
+
+    /// Use the given rule to reduce.
+    /// This pops elements off the stack equal to the number of symbols on the right hand side.
+    /// The machine then follows the GOTO transition for the left hand side.
+    Reduce(Rule<'g>),
+}
+
+/// Information on a conflict found in the given grammar.
+#[derive(Clone)]
+pub struct Conflict<'g, 't> {
+    table: &'t ParseTable<'g>,
+    state: StateIndex,
+    lookahead: Vec<Option<Symbol<'g>>>,
+    actions: Vec<Action<'g>>,
+}
+
+impl<'g> ParseTable<'g> {
+    /// Build a parse table from a grammar using `k` symbols of lookahead.
+    pub fn build(grammar: &'g Grammar, k: usize) -> ParseTable<'g> {
+        let analysis = GrammarAnalysis::build_k(grammar, k);
+        let states = Self::build_states(grammar, &analysis);
+        let gotos = Self::build_gotos(grammar, &analysis, &states);
+        let actions = Self::build_actions(&analysis, &states, &gotos);
+
+        ParseTable {
+            grammar,
+            k,
+            states,
+            actions,
+            gotos,
+        }
+    }
+
+    /// Get the underlying grammar for this parse table.
+    pub fn grammar(&self) -> &'g Grammar {
+        self.grammar
+    }
+
+    /// The number of symbols of lookahead used by this table.
+    pub fn k(&self) -> usize {
+        self.k
+    }
+
+    /// Get a slice of all of the states for this table.
+    pub fn states(&self) -> &[State<'g>] {
+        &self.states
+    }
+
+    fn build_states(
+        grammar: &'g Grammar,
+        analysis: &GrammarAnalysis<'g>,
+    ) -> Vec<State<'g>> {
+        let mut states = vec![];
+        let start_item = Item::new(grammar.start_rule(), 0, [vec![None]].into_iter().collect());
+        let start_state = State::singleton(start_item, analysis);
+        let mut states_remaining = vec![start_state];
+
+        while let Some(state) = states_remaining.pop() {
+            if states.contains(&state) {
+                continue;
+            }
+
+            for symbol in grammar.symbols() {
+                let next_state = state.follow(analysis, symbol);
+
+                if next_state.items().is_empty() {
+                    continue;
+                }
+
+                if !states.contains(&next_state) {
+                    states_remaining.push(next_state);
+                }
+            }
+
+            states.push(state);
+        }
+
+        states
+    }
+
+    fn build_gotos(
+        grammar: &'g Grammar,
+        analysis: &GrammarAnalysis<'g>,
+        states: &[State<'g>],
+    ) -> BTreeMap<(StateIndex, Symbol<'g>), StateIndex> {
+        let mut gotos = BTreeMap::new();
+
+        for (src_state_index, src_state) in states.iter().enumerate() {
+            for symbol in grammar.symbols() {
+                let dst_state = src_state.follow(analysis, symbol);
+                if dst_state.items().is_empty() {
+                    continue;
+                }
+
+                let dst_state_index = states.iter().position(|state| *state == dst_state).unwrap();
+                gotos.insert((StateIndex(src_state_index), symbol), StateIndex(dst_state_index));
+            }
+        }
+
+        gotos
+    }
+
+    fn build_actions(
+        analysis: &GrammarAnalysis<'g>,
+        states: &[State<'g>],
+        gotos: &BTreeMap<(StateIndex, Symbol<'g>), StateIndex>,
+    ) -> BTreeMap<(StateIndex, Vec<Option<Symbol<'g>>>), Vec<Action<'g>>> {
+        let mut actions: BTreeMap<_, Vec<Action<'g>>> = BTreeMap::new();
+
+        for (src_state_index, src_state) in states.iter().enumerate() {
+            let src_state_index = StateIndex(src_state_index);
+            for src_item in src_state.items() {
+                match src_item.next_symbol() {
+                    Some(symbol) if symbol.is_terminal() => {
+                        // We shift on any lookahead which could begin with the terminal.
+                        let mut seq = vec![symbol];
+                        seq.extend(src_item.rest());
+                        let action = Action::Shift(gotos[&(src_state_index, symbol)]);
+
+                        for lookahead in State::lookahead_after(analysis, &seq, src_item.lookahead()) {
+                            let actions_for = actions.entry((src_state_index, lookahead)).or_default();
+                            if !actions_for.contains(&action) {
+                                actions_for.push(action);
+                            }
+                        }
+                    }
+                    Some(_nonterminal) => (),
+                    None => {
+                        for lookahead in src_item.lookahead() {
+                            let actions_for = actions.entry((src_state_index, lookahead.clone())).or_default();
+                            actions_for.push(Action::Reduce(src_item.rule()));
+                        }
+                    }
+                }
+            }
+        }
+
+        actions
+    }
+
+    /// Return a list of all of the conflicts found in this table.
+    pub fn conflicts(&self) -> Vec<Conflict<'g, '_>> {
+        let mut conflicts = vec![];
+        for ((state_index, lookahead), actions) in &self.actions {
+            if actions.len() > 1 {
+                conflicts.push(Conflict {
+                    table: self,
+                    state: *state_index,
+                    lookahead: lookahead.clone(),
+                    actions: actions.clone(),
+                });
+            }
+        }
+        conflicts
+    }
+
+    /// The actions to take in the given state when `lookahead` is next in the input.
+    ///
+    /// The lookahead is the next `k` symbols of the input.
+    /// If there are fewer than `k` symbols left, it is the remaining symbols followed by `None`.
+    pub fn get(&self, state_index: StateIndex, lookahead: &[Option<Symbol<'g>>]) -> Vec<Action<'g>> {
+        let key = (state_index, lookahead.to_vec());
+        self.actions.get(&key).cloned().unwrap_or_default()
+    }
+
+    /// The state entered after reducing to `symbol` in the given state.
+    pub fn goto(&self, state_index: StateIndex, symbol: Symbol<'g>) -> Option<StateIndex> {
+        self.gotos.get(&(state_index, symbol)).copied()
+    }
+
+    /// The set of lookahead strings for which the given state has an action.
+    pub fn lookaheads(&self, state_index: StateIndex) -> BTreeSet<Vec<Option<Symbol<'g>>>> {
+        self.actions
+            .keys()
+            .filter(|(index, _lookahead)| *index == state_index)
+            .map(|(_index, lookahead)| lookahead.clone())
+            .collect()
+    }
+
+    pub fn dump(&self) {
+        for (state_index, state) in self.states.iter().enumerate() {
+            let state_index = StateIndex(state_index);
+            eprintln!("{state_index:?}");
+            eprintln!("{state:?}");
+
+            for lookahead in self.lookaheads(state_index) {
+                eprintln!("    {lookahead:?} => {:?}", self.get(state_index, &lookahead));
+            }
+            for symbol in self.grammar.nonterminals() {
+                if let Some(dst_state_index) = self.goto(state_index, symbol) {
+                    eprintln!("    goto {symbol:?} => {dst_state_index:?}");
+                }
+            }
+            eprintln!();
+        }
+    }
+}
+
+/// The smallest `k` at most `max_k` for which the grammar has a conflict-free LR(k) table.
+///
+/// Returns `None` if there are still conflicts with `max_k` symbols of lookahead.
+pub fn minimal_k(grammar: &Grammar, max_k: usize) -> Option<usize> {
+    (1..=max_k).find(|k| ParseTable::build(grammar, *k).conflicts().is_empty())
+}
+
+impl<'g, 't> std::fmt::Debug for Conflict<'g, 't> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state_id = self.state;
+        let lookahead = &self.lookahead;
+        let actions = &self.actions;
+        write!(f, "Conflict(state={state_id:?}, lookahead={lookahead:?}, actions={actions:?})")?;
+        Ok(())
+    }
+}
+
+impl<'g, 't> Conflict<'g, 't> {
+    pub fn table(&self) -> &'t ParseTable<'g> {
+        self.table
+    }
+
+    pub fn state(&self) -> &'t State<'g> {
+        &self.table.states[usize::from(self.state)]
+    }
+
+    pub fn lookahead(&self) -> &[Option<Symbol<'g>>] {
+        &self.lookahead
+    }
+
+    pub fn actions(&self) -> &[Action<'g>] {
+        &self.actions
+    }
+}
+
+impl<'g> std::ops::Index<StateIndex> for ParseTable<'g> {
+    type Output = State<'g>;
+
+    fn index(&self, index: StateIndex) -> &Self::Output {
+        &self.states[usize::from(index)]
+    }
+}
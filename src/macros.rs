@@ -39,11 +39,8 @@ macro_rules! rule {
     ) => {{
         'result: {
             let lhs = $grammar.symbol(stringify!($lhs)).unwrap();
-            let mut rhs = vec![];
-            $(
-                rhs.push($grammar.symbol(stringify!($rhs)).unwrap());
-            )*
-            for rule in $grammar.rules() {
+            let rhs = vec![$($grammar.symbol(stringify!($rhs)).unwrap()),*];
+            for rule in $grammar.rules_for(lhs) {
                 if rule.lhs() == lhs && rule.rhs() == rhs {
                     break 'result rule;
                 }
@@ -1,5 +1,7 @@
 use std::collections::{BTreeMap, BTreeSet};
 
+use crate::dfa::Dfa;
+
 #[derive(Debug)]
 pub struct Nfa {
     num_states: u32,
@@ -53,22 +55,45 @@ impl Nfa {
     }
 
     pub fn step(&mut self, symbol: SymbolIdx) -> &BTreeSet<StateIdx> {
-        let mut next_states = BTreeSet::new();
-        for from_state in &self.states {
-            let key = (*from_state, Some(symbol));
-            if self.transitions.contains_key(&key) {
-                let to_states = &self.transitions[&key];
-                next_states.extend(to_states);
+        self.states = self.closure(self.targets(&self.states, Some(symbol)));
+        &self.states
+    }
+
+    pub fn num_states(&self) -> usize {
+        self.num_states as usize
+    }
+
+    pub fn num_symbols(&self) -> usize {
+        self.num_symbols as usize
+    }
+
+    /// Does the machine, started afresh, end in one of the `accepting` states after reading `input`?
+    ///
+    /// This doesn't move the machine.
+    pub fn accepts(&self, accepting: &BTreeSet<StateIdx>, input: &[SymbolIdx]) -> bool {
+        let mut states = self.closure([0].into_iter().collect());
+        for symbol in input {
+            states = self.closure(self.targets(&states, Some(*symbol)));
+        }
+        states.iter().any(|state| accepting.contains(state))
+    }
+
+    /// The states reached from any of `states` by a single transition through `symbol`,
+    /// or by a single free transition when `symbol` is `None`.
+    pub fn targets(&self, states: &BTreeSet<StateIdx>, symbol: Option<SymbolIdx>) -> BTreeSet<StateIdx> {
+        let mut targets = BTreeSet::new();
+        for state in states {
+            if let Some(to_states) = self.transitions.get(&(*state, symbol)) {
+                targets.extend(to_states);
             }
         }
-        self.states = next_states.clone();
-        self.step_frees();
-        &self.states
+        targets
     }
 
-    fn step_frees(&mut self) {
-        let mut visited = self.states.clone();
-        let mut queue: Vec<StateIdx> = self.states.clone().into_iter().collect();
+    /// The states reachable from `states` through free transitions, including `states` themselves.
+    pub fn closure(&self, states: BTreeSet<StateIdx>) -> BTreeSet<StateIdx> {
+        let mut visited = states.clone();
+        let mut queue: Vec<StateIdx> = states.into_iter().collect();
 
         while let Some(from_state) = queue.pop() {
             let key = (from_state, None);
@@ -82,7 +107,44 @@ impl Nfa {
             }
         }
 
-        self.states = visited;
+        visited
+    }
+
+    /// Build a DFA for the same language by the subset construction.
+    ///
+    /// Each state of the DFA is the set of NFA states reachable on some input.
+    /// The empty set, if reachable, is a dead state.
+    /// Returns the DFA and its accepting states.
+    pub fn determinize(&self, accepting: &BTreeSet<StateIdx>) -> (Dfa, BTreeSet<StateIdx>) {
+        let start = self.closure([0].into_iter().collect());
+        let mut indices = BTreeMap::from([(start.clone(), 0)]);
+        let mut sets = vec![start];
+        let mut rows = vec![];
+
+        while rows.len() < sets.len() {
+            let mut row = vec![];
+            for symbol in 0..self.num_symbols() {
+                let next = self.closure(self.targets(&sets[rows.len()], Some(symbol)));
+                let index = match indices.get(&next) {
+                    Some(index) => *index,
+                    None => {
+                        indices.insert(next.clone(), sets.len());
+                        sets.push(next);
+                        sets.len() - 1
+                    }
+                };
+                row.push(index);
+            }
+            rows.push(row);
+        }
+
+        let dfa_accepting = sets
+            .iter()
+            .enumerate()
+            .filter(|(_index, set)| set.iter().any(|state| accepting.contains(state)))
+            .map(|(index, _set)| index)
+            .collect();
+        (Dfa::new(rows), dfa_accepting)
     }
 }
 
@@ -128,3 +190,23 @@ fn test3() {
     nfa.step(0);
     assert_eq!(nfa.states(), vec![]);
 }
+
+#[test]
+fn test_determinize() {
+    let mut nfa = Nfa::new(4, 2);
+    nfa.add_transition(0, 1, 0);
+    nfa.add_transition(0, 2, 0);
+    nfa.add_transition(1, 1, 1);
+    nfa.add_free_transition(2, 3);
+    nfa.add_transition(3, 3, 0);
+
+    let accepting = [1, 3].into_iter().collect();
+    let (dfa, dfa_accepting) = nfa.determinize(&accepting);
+    for input in [vec![], vec![0], vec![0, 1], vec![0, 0], vec![0, 1, 0], vec![1]] {
+        let mut state = 0;
+        for symbol in &input {
+            state = dfa.transition(state, *symbol);
+        }
+        assert_eq!(dfa_accepting.contains(&state), nfa.accepts(&accepting, &input), "{input:?}");
+    }
+}
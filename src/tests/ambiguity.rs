@@ -0,0 +1,60 @@
+use std::time::Duration;
+
+use crate::*;
+use crate::ambiguity::*;
+
+#[test]
+fn test_ambiguous() {
+    let grammar = grammar! {
+        S -> E;
+        E -> E plus E;
+        E -> id;
+    };
+    let id = grammar.symbol("id").unwrap();
+    let plus = grammar.symbol("plus").unwrap();
+
+    let Ambiguity::Found { sentence, trees: (first, second) } = AmbiguitySearch::new(&grammar).run() else {
+        panic!("Expected an ambiguity");
+    };
+    assert_eq!(sentence, vec![id, plus, id, plus, id]);
+    assert_ne!(first, second);
+    assert_eq!(first.leaves(), sentence);
+    assert_eq!(second.leaves(), sentence);
+    assert_eq!(first.symbol(), grammar.start_symbol());
+
+    // Too short to contain an ambiguity.
+    assert_eq!(AmbiguitySearch::new(&grammar).max_length(4).run(), Ambiguity::NotFound { up_to: Some(4) });
+}
+
+#[test]
+fn test_ambiguous_in_context() {
+    let grammar = grammar! {
+        S -> A;
+        A -> lparen B rparen;
+        B -> B;
+        B -> x;
+    };
+    let Ambiguity::Found { sentence, trees: (first, second) } = AmbiguitySearch::new(&grammar).run() else {
+        panic!("Expected an ambiguity");
+    };
+    assert_eq!(sentence.len(), 3);
+    assert_eq!(first.rules().len() + 1, second.rules().len());
+}
+
+#[test]
+fn test_unambiguous() {
+    let grammar = grammar! {
+        S -> E;
+        E -> T plus E;
+        E -> T;
+        T -> F times T;
+        T -> F;
+        F -> id;
+        F -> lparen E rparen;
+    };
+    assert_eq!(AmbiguitySearch::new(&grammar).max_length(7).run(), Ambiguity::NotFound { up_to: Some(7) });
+    assert_eq!(
+        AmbiguitySearch::new(&grammar).time_limit(Duration::ZERO).run(),
+        Ambiguity::NotFound { up_to: None },
+    );
+}
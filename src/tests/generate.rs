@@ -0,0 +1,285 @@
+use std::collections::BTreeSet;
+
+use crate::*;
+use crate::generate::*;
+
+fn expr_grammar() -> Grammar {
+    grammar! {
+        S -> E;
+        E -> T Emore;
+        Emore -> plus T Emore;
+        Emore -> ;
+        T -> F Tmore ;
+        Tmore -> times F Tmore ;
+        Tmore -> ;
+        F -> id;
+        F -> lparen E rparen;
+    }
+}
+
+#[test]
+fn test_rng() {
+    let mut rng1 = Rng::new(7);
+    let mut rng2 = Rng::new(7);
+    for _ in 0..100 {
+        assert_eq!(rng1.next_u64(), rng2.next_u64());
+        assert!(rng1.below(10) < 10);
+        rng2.below(10);
+    }
+    assert_ne!(Rng::new(1).next_u64(), Rng::new(2).next_u64());
+}
+
+#[test]
+fn test_generate() {
+    let grammar = expr_grammar();
+    let table = ll1::ParseTable::build(&grammar, grammar.start_symbol());
+
+    let mut generator = Generator::new(&grammar).seed(42).max_length(15);
+    let mut lengths = vec![];
+    for _ in 0..50 {
+        let sentence = generator.generate();
+        assert!(sentence.len() <= 15);
+        assert!(ll1::Machine::new(&table, sentence.clone().into_iter()).run().is_ok());
+        lengths.push(sentence.len());
+    }
+    assert!(lengths.iter().any(|length| *length > 1));
+
+    let mut generator1 = Generator::new(&grammar).seed(3);
+    let mut generator2 = Generator::new(&grammar).seed(3);
+    for _ in 0..10 {
+        assert_eq!(generator1.generate(), generator2.generate());
+    }
+}
+
+#[test]
+fn test_generate_limits() {
+    let grammar = expr_grammar();
+    let id = grammar.symbol("id").unwrap();
+
+    // At depth 0, everything falls back on the shortest yield.
+    let mut generator = Generator::new(&grammar).max_depth(0);
+    assert_eq!(generator.generate(), vec![id]);
+
+    // With no room to grow, the same happens.
+    let mut generator = Generator::new(&grammar).max_length(1);
+    for _ in 0..10 {
+        assert_eq!(generator.generate(), vec![id]);
+    }
+}
+
+#[test]
+fn test_generate_weights() {
+    let grammar = expr_grammar();
+    let plus = grammar.symbol("plus").unwrap();
+    let times = grammar.symbol("times").unwrap();
+
+    let mut generator = Generator::new(&grammar)
+        .seed(5)
+        .weight(rule!(grammar, Emore -> plus T Emore), 0)
+        .weight(rule!(grammar, Tmore -> times F Tmore), 10);
+
+    let mut saw_times = false;
+    for _ in 0..20 {
+        let sentence = generator.generate();
+        assert!(!sentence.contains(&plus));
+        saw_times |= sentence.contains(&times);
+    }
+    assert!(saw_times);
+
+    let f = grammar.symbol("F").unwrap();
+    let tree = generator.generate_tree_from(f);
+    assert_eq!(tree.symbol(), f);
+}
+
+#[test]
+fn test_rule_coverage() {
+    let grammar = expr_grammar();
+    let table = ll1::ParseTable::build(&grammar, grammar.start_symbol());
+
+    let trees = rule_coverage(&grammar);
+    assert!(trees.len() < grammar.rules().len());
+
+    let mut used = BTreeSet::new();
+    for tree in &trees {
+        assert!(ll1::Machine::new(&table, tree.leaves().into_iter()).run().is_ok());
+        used.extend(tree.rules());
+    }
+    assert_eq!(used, grammar.rules().into_iter().collect());
+}
+
+#[test]
+fn test_rule_coverage_useless() {
+    let grammar = grammar! {
+        S -> A;
+        A -> x;
+        A -> B y;
+        A -> C;
+        B -> B z;
+        C -> w;
+        D -> x;
+    };
+
+    let mut used = BTreeSet::new();
+    for tree in rule_coverage(&grammar) {
+        used.extend(tree.rules());
+    }
+    let expected = BTreeSet::from([
+        rule!(grammar, S -> A),
+        rule!(grammar, A -> x),
+        rule!(grammar, A -> C),
+        rule!(grammar, C -> w),
+    ]);
+    assert_eq!(used, expected);
+}
+
+#[test]
+fn test_transition_coverage() {
+    let grammar = grammar! {
+        S -> E;
+        E -> T plus E;
+        E -> T;
+        T -> F times T;
+        T -> F;
+        F -> id;
+        F -> lparen E rparen;
+    };
+    let table = lr1::ParseTable::build(&grammar);
+
+    // Every transition out of a state the parser can enter.
+    let mut expected = BTreeSet::new();
+    let mut stack = vec![lr1::StateIndex(0)];
+    while let Some(state) = stack.pop() {
+        for symbol in grammar.symbols() {
+            if let [lr1::Action::Shift(next)] = table.get(state, Some(symbol))[..] &&
+                expected.insert((state, symbol)) {
+                stack.push(next);
+            }
+        }
+    }
+
+    let trees = transition_coverage(&table);
+    assert!(trees.len() < expected.len());
+
+    let mut taken = BTreeSet::new();
+    for tree in &trees {
+        lr1::Machine::new(&table, tree.leaves().into_iter()).run();
+        take_transitions(&table, tree, &mut vec![], &mut taken);
+    }
+    assert_eq!(taken, expected);
+}
+
+// Replay the shifts and GOTOs the parser makes while building `tree`.
+fn take_transitions<'g>(
+    table: &lr1::ParseTable<'g>,
+    tree: &ParseTree<'g>,
+    stack: &mut Vec<lr1::StateIndex>,
+    taken: &mut BTreeSet<(lr1::StateIndex, Symbol<'g>)>,
+) {
+    for child in tree.children() {
+        take_transitions(table, child, stack, taken);
+    }
+    stack.truncate(stack.len() - tree.children().len());
+    if tree.rule() == Some(table.grammar().start_rule()) {
+        return;
+    }
+
+    let state = stack.last().copied().unwrap_or(lr1::StateIndex(0));
+    let [lr1::Action::Shift(next)] = table.get(state, Some(tree.symbol()))[..] else {
+        panic!("Expected a transition from {state:?} on {:?}", tree.symbol());
+    };
+    taken.insert((state, tree.symbol()));
+    stack.push(next);
+}
+
+#[test]
+fn test_near_misses() {
+    let grammar = grammar! {
+        S -> E;
+        E -> T plus E;
+        E -> T;
+        T -> F times T;
+        T -> F;
+        F -> id;
+        F -> lparen E rparen;
+    };
+    let table = lr1::ParseTable::build(&grammar);
+    let id = grammar.symbol("id").unwrap();
+    let plus = grammar.symbol("plus").unwrap();
+    let times = grammar.symbol("times").unwrap();
+    let lparen = grammar.symbol("lparen").unwrap();
+
+    let sentence = vec![id, plus, id];
+    let near_misses = near_misses(&table, &sentence);
+    assert!(!near_misses.is_empty());
+
+    let mut sentences = BTreeSet::new();
+    for near_miss in &near_misses {
+        assert_eq!(near_miss.mutation().apply(&sentence), near_miss.sentence());
+        assert!(near_miss.error_position() <= near_miss.sentence().len());
+        assert!(!near_miss.expected().is_empty());
+        assert!(sentences.insert(near_miss.sentence().to_vec()));
+    }
+
+    // Replacing `plus` by `times` gives a valid sentence.
+    assert!(!sentences.contains(&vec![id, times, id]));
+
+    let deleted = near_misses.iter().find(|near_miss| near_miss.mutation() == Mutation::Delete(2)).unwrap();
+    assert_eq!(deleted.sentence(), [id, plus]);
+    assert_eq!(deleted.error_position(), 2);
+    assert_eq!(deleted.expected(), &BTreeSet::from([Some(id), Some(lparen)]));
+
+    // Inserting `id` at position 1 gives the same sentence, so only the first is reported.
+    assert!(!near_misses.iter().any(|near_miss| near_miss.mutation() == Mutation::Insert(1, id)));
+    let inserted = near_misses.iter().find(|near_miss| near_miss.mutation() == Mutation::Insert(0, id)).unwrap();
+    assert_eq!(inserted.sentence(), [id, id, plus, id]);
+    assert_eq!(inserted.error_position(), 1);
+    assert_eq!(inserted.expected(), &BTreeSet::from([None, Some(plus), Some(times)]));
+}
+
+#[test]
+fn test_enumerate() {
+    let grammar = grammar! {
+        S -> A;
+        A -> lparen A rparen A;
+        A -> ;
+    };
+    let lparen = grammar.symbol("lparen").unwrap();
+    let rparen = grammar.symbol("rparen").unwrap();
+
+    let sentences = enumerate(&grammar, 4);
+    assert_eq!(
+        sentences,
+        vec![
+            vec![],
+            vec![lparen, rparen],
+            vec![lparen, lparen, rparen, rparen],
+            vec![lparen, rparen, lparen, rparen],
+        ],
+    );
+
+    // The Catalan numbers, with nothing of odd length.
+    assert_eq!(count_sentences(&grammar, 8), vec![1, 0, 1, 0, 2, 0, 5, 0, 14]);
+    assert_eq!(count_derivations(&grammar, 8), vec![1, 0, 1, 0, 2, 0, 5, 0, 14]);
+    assert_eq!(count_derivations(&grammar, 40)[40], 6564120420);
+    assert_eq!(first_ambiguous_length(&grammar, 8), None);
+}
+
+#[test]
+fn test_count_ambiguous() {
+    let grammar = grammar! {
+        S -> E;
+        E -> E plus E;
+        E -> id;
+    };
+    assert_eq!(count_sentences(&grammar, 5), vec![0, 1, 0, 1, 0, 1]);
+    assert_eq!(count_derivations(&grammar, 5), vec![0, 1, 0, 1, 0, 2]);
+    assert_eq!(first_ambiguous_length(&grammar, 5), Some(5));
+
+    let cyclic = grammar! {
+        S -> A;
+        A -> A;
+        A -> x;
+    };
+    assert_eq!(count_sentences(&cyclic, 2), vec![0, 1, 0]);
+    assert_eq!(count_derivations(&cyclic, 2), vec![0, u128::MAX, 0]);
+}
@@ -286,6 +286,29 @@ fn test_is_terminal() {
     assert!(x.is_terminal());
 }
 
+#[test]
+fn test_symbol_rules() {
+    let grammar = grammar! {
+        S -> A;
+        A -> A x;
+        A -> ;
+        B -> x;
+    };
+
+    let s = grammar.symbol("S").unwrap();
+    let a = grammar.symbol("A").unwrap();
+    let b = grammar.symbol("B").unwrap();
+    let x = grammar.symbol("x").unwrap();
+
+    assert_eq!(s.rules(), vec![rule!(grammar, S -> A)]);
+    assert_eq!(a.rules(), vec![rule!(grammar, A -> A x), rule!(grammar, A -> )]);
+    assert_eq!(grammar.rules_for(b), vec![rule!(grammar, B -> x)]);
+    assert_eq!(x.rules(), vec![]);
+
+    assert_eq!(grammar.symbol("x"), Some(x));
+    assert_eq!(grammar.symbol("z"), None);
+}
+
 #[test]
 fn test_is_nullable_seq() {
     let grammar = grammar! {
@@ -365,6 +388,82 @@ fn test_can_end_with() {
     assert!(analysis.can_end_with(c, c));
 }
 
+#[test]
+fn test_first_k() {
+    let grammar = grammar! {
+        S -> A;
+        A -> x A y;
+        A -> z;
+        A -> ;
+    };
+
+    let a = grammar.symbol("A").unwrap();
+    let x = grammar.symbol("x").unwrap();
+    let y = grammar.symbol("y").unwrap();
+    let z = grammar.symbol("z").unwrap();
+
+    let analysis = GrammarAnalysis::build_k(&grammar, 2);
+
+    let expected: BTreeSet<Vec<Symbol>> = [vec![], vec![z], vec![x, x], vec![x, z], vec![x, y]].into_iter().collect();
+    assert_eq!(analysis.first_k(&[a]), expected);
+
+    let expected: BTreeSet<Vec<Symbol>> = [vec![y], vec![z, y], vec![x, x], vec![x, z], vec![x, y]].into_iter().collect();
+    assert_eq!(analysis.first_k(&[a, y]), expected);
+
+    assert_eq!(analysis.first_k(&[]), [vec![]].into_iter().collect());
+}
+
+#[test]
+fn test_follow_k() {
+    let grammar = grammar! {
+        S -> A;
+        A -> x A y;
+        A -> z;
+        A -> ;
+    };
+
+    let s = grammar.symbol("S").unwrap();
+    let a = grammar.symbol("A").unwrap();
+    let y = grammar.symbol("y").unwrap();
+
+    let analysis = GrammarAnalysis::build_k(&grammar, 2);
+
+    assert_eq!(analysis.follow_k(s), [vec![None]].into_iter().collect());
+
+    let expected: BTreeSet<Vec<Option<Symbol>>> = [vec![None], vec![Some(y), None], vec![Some(y), Some(y)]].into_iter().collect();
+    assert_eq!(analysis.follow_k(a), expected);
+}
+
+#[test]
+fn test_first_k_agrees_with_first() {
+    let grammar = grammar! {
+        S -> E;
+        E -> T Emore;
+        Emore -> plus T Emore;
+        Emore -> ;
+        T -> F Tmore ;
+        Tmore -> times F Tmore ;
+        Tmore -> ;
+        F -> id;
+        F -> lparen E rparen;
+    };
+
+    let analysis = GrammarAnalysis::build(&grammar);
+    let start_symbol = grammar.start_symbol();
+
+    for symbol in grammar.nonterminals() {
+        let first: BTreeSet<Vec<Symbol>> = analysis.first(symbol).into_iter().map(|terminal| vec![terminal]).collect();
+        let first_k: BTreeSet<Vec<Symbol>> = analysis.first_k(&[symbol]).into_iter().filter(|string| !string.is_empty()).collect();
+        assert_eq!(first, first_k);
+
+        let mut follow: BTreeSet<Vec<Option<Symbol>>> = analysis.follow(symbol).into_iter().map(|terminal| vec![Some(terminal)]).collect();
+        if analysis.can_end_with(start_symbol, symbol) {
+            follow.insert(vec![None]);
+        }
+        assert_eq!(follow, analysis.follow_k(symbol));
+    }
+}
+
 #[test]
 fn ll1_example1() {
     let grammar = grammar! {
@@ -388,7 +487,360 @@ fn ll1_example1() {
         grammar.symbol("id").unwrap(),
         grammar.symbol("times").unwrap(),
         grammar.symbol("id").unwrap(),
-    ].into_iter();
-    let mut machine = ll1::Machine::new(table, grammar.symbol("E").unwrap(), input);
-    machine.run();
+    ];
+    let mut machine = ll1::Machine::new(&table, input.clone().into_iter());
+    let tree = machine.run().unwrap();
+    assert_eq!(tree.leaves(), input);
+}
+
+#[test]
+fn test_useless_symbols() {
+    let grammar = grammar! {
+        S -> A;
+        A -> x B;
+        A -> y;
+        B -> B z;
+        C -> c;
+    };
+
+    let analysis = GrammarAnalysis::build(&grammar);
+    let b = grammar.symbol("B").unwrap();
+    let c = grammar.symbol("C").unwrap();
+
+    assert_eq!(analysis.unproductive(), [b].into_iter().collect());
+    assert_eq!(analysis.unreachable(), [c].into_iter().collect());
+    assert_eq!(analysis.unused_terminals(), [grammar.symbol("c").unwrap()].into_iter().collect());
+}
+
+#[test]
+fn test_dependency_graph() {
+    let grammar = grammar! {
+        S -> Items;
+        Items -> Items Item;
+        Items -> ;
+        Item -> Expr semicolon;
+        Expr -> Term plus Expr;
+        Expr -> Term;
+        Term -> lparen Expr rparen;
+        Term -> id;
+    };
+
+    let s = grammar.symbol("S").unwrap();
+    let items = grammar.symbol("Items").unwrap();
+    let item = grammar.symbol("Item").unwrap();
+    let expr = grammar.symbol("Expr").unwrap();
+    let term = grammar.symbol("Term").unwrap();
+
+    let analysis = GrammarAnalysis::build(&grammar);
+    let graph = analysis.dependency_graph();
+    assert_eq!(graph.dependencies(items), [items, item].into_iter().collect());
+    assert_eq!(graph.dependents(expr), [item, expr, term].into_iter().collect());
+
+    let sccs = graph.sccs();
+    assert_eq!(
+        sccs,
+        vec![
+            [expr, term].into_iter().collect(),
+            [item].into_iter().collect(),
+            [items].into_iter().collect(),
+            [s].into_iter().collect(),
+        ],
+    );
+
+    let dot = graph.to_dot();
+    assert!(dot.starts_with("digraph dependencies {\n"));
+    assert!(dot.contains("    \"Items\" -> \"Item\";\n"));
+    assert!(dot.contains("    subgraph cluster_0 {\n        \"Expr\";\n        \"Term\";\n    }\n"));
+}
+
+#[test]
+fn test_recursion() {
+    let grammar = grammar! {
+        S -> A;
+        A -> B A x;
+        A -> y;
+        B -> ;
+        B -> b;
+        C -> x D;
+        D -> C;
+        D -> E;
+        E -> D;
+    };
+
+    let a = grammar.symbol("A").unwrap();
+    let c = grammar.symbol("C").unwrap();
+    let d = grammar.symbol("D").unwrap();
+    let e = grammar.symbol("E").unwrap();
+
+    let analysis = GrammarAnalysis::build(&grammar);
+
+    // A -> B A x is left recursive, since B is nullable.
+    let left = analysis.left_recursion();
+    let symbols: Vec<Symbol> = left.iter().map(|recursion| recursion.symbol()).collect();
+    assert_eq!(symbols, vec![a, d, e]);
+    assert!(left[0].is_direct());
+    assert_eq!(left[0].rules(), &[rule!(grammar, A -> B A x)]);
+    assert_eq!(left[0].kind(), RecursionKind::Left);
+
+    let right = analysis.right_recursion();
+    let symbols: Vec<Symbol> = right.iter().map(|recursion| recursion.symbol()).collect();
+    assert_eq!(symbols, vec![c, d, e]);
+    assert_eq!(right[0].rules(), &[rule!(grammar, C -> x D), rule!(grammar, D -> C)]);
+    assert!(!right[0].is_direct());
+
+    let cycles = analysis.cycles();
+    assert_eq!(cycles.len(), 2);
+    assert_eq!(cycles[0].rules(), &[rule!(grammar, D -> E), rule!(grammar, E -> D)]);
+    assert_eq!(cycles[1].rules(), &[rule!(grammar, E -> D), rule!(grammar, D -> E)]);
+}
+
+#[test]
+fn test_explain() {
+    let grammar = grammar! {
+        S -> A;
+        A -> B C d;
+        B -> ;
+        B -> b;
+        C -> D;
+        D -> ;
+        D -> c;
+        E -> A e;
+    };
+
+    let a = grammar.symbol("A").unwrap();
+    let b = grammar.symbol("B").unwrap();
+    let c = grammar.symbol("C").unwrap();
+    let d = grammar.symbol("d").unwrap();
+    let e = grammar.symbol("e").unwrap();
+
+    let analysis = GrammarAnalysis::build(&grammar);
+
+    assert_eq!(analysis.explain_nullable(c), Some(vec![rule!(grammar, C -> D), rule!(grammar, D ->)]));
+    assert_eq!(analysis.explain_nullable(a), None);
+
+    assert_eq!(
+        analysis.explain_first(a, grammar.symbol("c").unwrap()),
+        Some(vec![rule!(grammar, A -> B C d), rule!(grammar, B ->), rule!(grammar, C -> D), rule!(grammar, D -> c)]),
+    );
+    assert_eq!(
+        analysis.explain_first(a, d),
+        Some(vec![rule!(grammar, A -> B C d), rule!(grammar, B ->), rule!(grammar, C -> D), rule!(grammar, D ->)]),
+    );
+    assert_eq!(analysis.explain_first(a, e), None);
+
+    assert_eq!(
+        analysis.explain_follow(b, d),
+        Some(vec![rule!(grammar, A -> B C d), rule!(grammar, C -> D), rule!(grammar, D ->)]),
+    );
+    let d_symbol = grammar.symbol("D").unwrap();
+    assert_eq!(analysis.explain_follow(d_symbol, d), Some(vec![rule!(grammar, C -> D), rule!(grammar, A -> B C d)]));
+    assert_eq!(analysis.explain_follow(d_symbol, e), None);
+    assert_eq!(analysis.explain_follow(a, e), Some(vec![rule!(grammar, E -> A e)]));
+}
+
+#[test]
+fn test_shortest_yield() {
+    let grammar = grammar! {
+        S -> E;
+        E -> E plus T;
+        E -> T;
+        T -> T times F;
+        T -> F;
+        F -> lparen E rparen;
+        F -> id;
+        U -> U x;
+    };
+
+    let analysis = GrammarAnalysis::build(&grammar);
+    let e = grammar.symbol("E").unwrap();
+    let f = grammar.symbol("F").unwrap();
+    let id = grammar.symbol("id").unwrap();
+
+    assert_eq!(analysis.shortest_yield(grammar.start_symbol()), vec![id]);
+    assert_eq!(format!("{:?}", analysis.shortest_derivation(e)), "(E (T (F id)))");
+    assert_eq!(analysis.shortest_yield(f), vec![id]);
+    assert_eq!(analysis.shortest_yield(id), vec![id]);
+
+    let grammar = grammar! {
+        S -> A;
+        A -> x x x;
+        A -> B B;
+        B -> y;
+        B -> ;
+    };
+    let analysis = GrammarAnalysis::build(&grammar);
+    assert_eq!(format!("{:?}", analysis.shortest_derivation(grammar.start_symbol())), "(S (A (B) (B)))");
+}
+
+#[test]
+#[should_panic]
+fn test_shortest_yield_unproductive() {
+    let grammar = grammar! {
+        S -> U;
+        U -> U x;
+    };
+    let analysis = GrammarAnalysis::build(&grammar);
+    analysis.shortest_yield(grammar.start_symbol());
+}
+
+// Check that the NFA accepts exactly the sentences of the grammar, up to `max_length` terminals.
+fn assert_nfa_matches(grammar: &Grammar, max_length: usize) {
+    let analysis = GrammarAnalysis::build(grammar);
+    let (nfa, accepting) = analysis.to_nfa().unwrap();
+    let sentences: BTreeSet<Vec<usize>> = generate::enumerate(grammar, max_length)
+        .into_iter()
+        .map(|sentence| sentence.iter().map(|symbol| usize::from(symbol.index())).collect())
+        .collect();
+
+    let terminals: Vec<usize> = grammar.terminals().iter().map(|symbol| usize::from(symbol.index())).collect();
+    let mut strings = vec![vec![]];
+    let mut start = 0;
+    for _ in 0..max_length {
+        let end = strings.len();
+        for i in start..end {
+            for terminal in &terminals {
+                let mut string = strings[i].clone();
+                string.push(*terminal);
+                strings.push(string);
+            }
+        }
+        start = end;
+    }
+
+    for string in strings {
+        assert_eq!(nfa.accepts(&accepting, &string), sentences.contains(&string), "{string:?}");
+    }
+}
+
+#[test]
+fn test_language_properties() {
+    let grammar = grammar! {
+        S -> A;
+        A -> x B;
+        B -> y;
+        B -> ;
+    };
+    let analysis = GrammarAnalysis::build(&grammar);
+    let x = grammar.symbol("x").unwrap();
+    let y = grammar.symbol("y").unwrap();
+    assert!(!analysis.is_empty());
+    assert!(analysis.is_finite());
+    assert_eq!(analysis.finite_language(), Some(vec![vec![x], vec![x, y]]));
+    assert!(!analysis.is_self_embedding());
+
+    let empty = grammar! {
+        S -> A;
+        A -> A x;
+    };
+    let analysis = GrammarAnalysis::build(&empty);
+    assert!(analysis.is_empty());
+    assert!(analysis.is_finite());
+    assert_eq!(analysis.finite_language(), Some(vec![]));
+
+    let parens = grammar! {
+        S -> A;
+        A -> lparen A rparen A;
+        A -> ;
+    };
+    let analysis = GrammarAnalysis::build(&parens);
+    assert!(!analysis.is_finite());
+    assert_eq!(analysis.finite_language(), None);
+    assert!(analysis.is_self_embedding());
+    assert!(analysis.to_nfa().is_none());
+
+    // Cycles which add nothing don't make the language infinite.
+    let cyclic = grammar! {
+        S -> A;
+        A -> A;
+        A -> B A;
+        A -> x;
+        B -> ;
+    };
+    let analysis = GrammarAnalysis::build(&cyclic);
+    assert!(analysis.is_finite());
+    assert_eq!(analysis.finite_language(), Some(vec![vec![cyclic.symbol("x").unwrap()]]));
+}
+
+#[test]
+fn test_to_nfa() {
+    // Right-linear.
+    assert_nfa_matches(&grammar! {
+        S -> A;
+        A -> a A;
+        A -> b B;
+        B -> c B;
+        B -> ;
+    }, 5);
+
+    // Left-linear, with ε-rules.
+    assert_nfa_matches(&grammar! {
+        S -> L;
+        L -> L x;
+        L -> L O y;
+        L -> z;
+        O -> ;
+        O -> o;
+    }, 5);
+
+    // Mixed, with mutual recursion and a finite part.
+    assert_nfa_matches(&grammar! {
+        S -> P;
+        P -> Q R;
+        Q -> Q T;
+        Q -> ;
+        T -> a b;
+        T -> c;
+        R -> b R2;
+        R2 -> a R;
+        R2 -> ;
+    }, 6);
+}
+
+#[test]
+fn test_regular_approximation() {
+    let parens = grammar! {
+        S -> A;
+        A -> lparen A rparen A;
+        A -> ;
+    };
+    let lparen = usize::from(parens.symbol("lparen").unwrap().index());
+    let rparen = usize::from(parens.symbol("rparen").unwrap().index());
+    let analysis = GrammarAnalysis::build(&parens);
+
+    let (nfa, accepting) = analysis.regular_approximation();
+    for sentence in generate::enumerate(&parens, 6) {
+        let sentence: Vec<usize> = sentence.iter().map(|symbol| usize::from(symbol.index())).collect();
+        assert!(nfa.accepts(&accepting, &sentence));
+    }
+    // The brackets no longer have to match.
+    assert!(nfa.accepts(&accepting, &[rparen, lparen, lparen]));
+
+    let (dfa, accepting) = analysis.regular_approximation_dfa();
+    assert_eq!(dfa.num_states(), 2);
+    assert!(accepting.contains(&dfa.transition(0, rparen)));
+
+    // A grammar which is already regular is approximated exactly.
+    let regular = grammar! {
+        S -> A;
+        A -> a A;
+        A -> b B;
+        B -> B c;
+        B -> ;
+    };
+    let a = usize::from(regular.symbol("a").unwrap().index());
+    let b = usize::from(regular.symbol("b").unwrap().index());
+    let c = usize::from(regular.symbol("c").unwrap().index());
+    let (dfa, accepting) = GrammarAnalysis::build(&regular).regular_approximation_dfa();
+    let accepts = |input: &[usize]| {
+        let mut state = 0;
+        for symbol in input {
+            state = dfa.transition(state, *symbol);
+        }
+        accepting.contains(&state)
+    };
+    assert!(accepts(&[a, a, b, c]));
+    assert!(accepts(&[b]));
+    assert!(!accepts(&[a]));
+    assert!(!accepts(&[b, a]));
+    assert!(!accepts(&[c]));
 }
@@ -0,0 +1,189 @@
+use std::collections::BTreeSet;
+
+use crate::*;
+use crate::language::*;
+
+fn expr_grammar() -> Grammar {
+    grammar! {
+        S -> E;
+        E -> E plus T;
+        E -> T;
+        T -> T times F;
+        T -> F;
+        F -> id;
+        F -> lparen E rparen;
+    }
+}
+
+#[test]
+fn test_compare_transformed() {
+    let grammar = expr_grammar();
+
+    let transformed = grammar.without_left_recursion();
+    assert!(compare_languages(&grammar, transformed.grammar(), 7).is_equal());
+
+    let transformed = grammar.to_cnf();
+    assert!(compare_languages(&grammar, transformed.grammar(), 7).is_equal());
+}
+
+#[test]
+fn test_compare_different() {
+    let grammar = expr_grammar();
+    let without_parens = grammar! {
+        S -> E;
+        E -> E plus T;
+        E -> T;
+        T -> T times F;
+        T -> F;
+        F -> id;
+    };
+
+    let comparison = compare_languages(&without_parens, &grammar, 5);
+    assert!(!comparison.is_equal());
+    assert!(comparison.is_first_included());
+    assert!(!comparison.is_second_included());
+    assert_eq!(comparison.only_in_first(), None);
+    assert_eq!(comparison.only_in_second().unwrap().len(), 3);
+    assert_eq!(comparison.distinguishing_sentence(), Some(vec!["lparen".to_string(), "id".to_string(), "rparen".to_string()]));
+
+    // The difference is beyond the bound.
+    assert!(compare_languages(&without_parens, &grammar, 2).is_equal());
+}
+
+// A DFA over the symbols of `grammar` which moves to a dead state 1 on any of the `rejected` terminals.
+fn dfa_without(grammar: &Grammar, rejected: &[&str]) -> dfa::Dfa {
+    let row = |state| {
+        grammar
+            .symbols()
+            .iter()
+            .map(|symbol| if rejected.contains(&symbol.name().as_str()) { 1 } else { state })
+            .collect()
+    };
+    dfa::Dfa::new(vec![row(0), vec![1; grammar.symbols().len()]])
+}
+
+#[test]
+fn test_intersect() {
+    let grammar = expr_grammar();
+    let without_parens = grammar! {
+        S -> E;
+        E -> E plus T;
+        E -> T;
+        T -> T times F;
+        T -> F;
+        F -> id;
+    };
+
+    let dfa = dfa_without(&grammar, &["lparen", "rparen"]);
+    let intersection = grammar.intersect(&dfa, &BTreeSet::from([0]));
+    assert!(compare_languages(&intersection, &without_parens, 7).is_equal());
+    assert_eq!(intersection.symbol("lparen"), None);
+    assert!(GrammarAnalysis::build(&intersection).unproductive().is_empty());
+    assert!(GrammarAnalysis::build(&intersection).unreachable().is_empty());
+
+    // Sentences which don't end in `id`.
+    let id = usize::from(grammar.symbol("id").unwrap().index());
+    let mut rows = vec![vec![0; grammar.symbols().len()], vec![0; grammar.symbols().len()]];
+    rows[0][id] = 1;
+    rows[1][id] = 1;
+    let intersection = grammar.intersect(&dfa::Dfa::new(rows), &BTreeSet::from([0]));
+    let sentences = generate::enumerate(&intersection, 5);
+    assert_eq!(sentences.len(), 6);
+    assert!(sentences.iter().all(|sentence| sentence.last().unwrap().name() == "rparen"));
+    let names: Vec<String> = sentences[0].iter().map(|symbol| symbol.name()).collect();
+    assert_eq!(names, vec!["lparen", "id", "rparen"]);
+}
+
+#[test]
+fn test_intersect_empty() {
+    let grammar = expr_grammar();
+    let dfa = dfa_without(&grammar, &["id"]);
+    let intersection = grammar.intersect(&dfa, &BTreeSet::from([0]));
+    assert!(GrammarAnalysis::build(&intersection).is_empty());
+    assert!(generate::enumerate(&intersection, 5).is_empty());
+}
+
+fn sentences(grammar: &Grammar, max_length: usize) -> Vec<String> {
+    generate::enumerate(grammar, max_length)
+        .iter()
+        .map(|sentence| sentence.iter().map(|symbol| symbol.name()).collect::<Vec<_>>().join(" "))
+        .collect()
+}
+
+#[test]
+fn test_union_concat_star() {
+    let ab = grammar! {
+        S -> A;
+        A -> a A b;
+        A -> ;
+    };
+    let c = grammar! {
+        S -> A;
+        A -> c;
+    };
+
+    let union = ab.union(&c);
+    assert_eq!(union.start_symbol().name(), "Start");
+    assert!(union.symbol("second_S").is_some());
+    assert!(union.symbol("second_A").is_some());
+    assert_eq!(sentences(&union, 4), vec!["", "c", "a b", "a a b b"]);
+
+    let concat = ab.concat(&c);
+    assert_eq!(sentences(&concat, 5), vec!["c", "a b c", "a a b b c"]);
+
+    let star = c.star();
+    assert_eq!(sentences(&star, 3), vec!["", "c", "c c", "c c c"]);
+
+    // A nonterminal named after a terminal of the other grammar is renamed.
+    let clash = grammar! {
+        S -> c;
+        c -> a;
+    };
+    let union = clash.union(&c);
+    assert!(union.symbol("first_c").is_some_and(|symbol| symbol.is_nonterminal()));
+    assert!(union.symbol("c").is_some_and(|symbol| symbol.is_terminal()));
+    assert_eq!(sentences(&union, 1), vec!["a", "c"]);
+}
+
+#[test]
+fn test_reversed() {
+    let grammar = expr_grammar();
+    let reversed = grammar.reversed();
+    assert_eq!(reversed.symbols().len(), grammar.symbols().len());
+    assert_eq!(sentences(&reversed, 3), vec!["id", "id plus id", "id times id", "rparen id lparen"]);
+    assert!(compare_languages(&grammar, &reversed.reversed(), 7).is_equal());
+}
+
+#[test]
+fn test_substitute() {
+    let list = grammar! {
+        S -> L;
+        L -> x;
+        L -> L comma x;
+    };
+    let pair = grammar! {
+        S -> L;
+        L -> lparen a b rparen;
+    };
+
+    let substituted = list.substitute("x", &pair);
+    assert!(substituted.symbol("x").is_none());
+    assert!(substituted.symbol("x_S").is_some());
+    assert!(substituted.symbol("x_L").is_some());
+    assert_eq!(
+        sentences(&substituted, 9),
+        vec!["lparen a b rparen", "lparen a b rparen comma lparen a b rparen"],
+    );
+
+    let substituted = list.substitute_form("x", &["a", "b"]);
+    assert_eq!(sentences(&substituted, 5), vec!["a b", "a b comma a b"]);
+
+    // Erasing the only symbol of the start rule gives a new start rule.
+    let erased = list.substitute_form("x", &[]).substitute_form("comma", &[]);
+    assert_eq!(sentences(&erased, 1), vec![""]);
+
+    let single = Grammar::new().symbol("S").symbol("x").rule("S", &["x"]).build();
+    let erased = single.substitute_form("x", &[]);
+    assert_eq!(erased.start_symbol().name(), "Start");
+    assert_eq!(sentences(&erased, 1), vec![""]);
+}
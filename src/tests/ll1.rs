@@ -1,19 +1,333 @@
-/*
-use crate::{ll1::ParseTable, *};
+use crate::*;
+use crate::ll1::*;
 
 #[test]
-fn debug_for_items2() {
+fn test_no_conflicts() {
     let grammar = grammar! {
-        start -> S;
-        S -> C C;
-        C -> c C;
-        C -> d;
+        S -> E;
+        E -> T Emore;
+        Emore -> plus T Emore;
+        Emore -> ;
+        T -> F Tmore ;
+        Tmore -> times F Tmore ;
+        Tmore -> ;
+        F -> id;
+        F -> lparen E rparen;
     };
 
-    let rule0 = grammar.rules()[0];
-
-    let table = ParseTable::build(&grammar, rule0);
+    let table = ParseTable::build(&grammar, grammar.start_symbol());
     assert_eq!(table.conflicts().len(), 0);
-    assert_eq!(table.states.len(), 14);
 }
-*/
+
+#[test]
+fn test_nullable_rhs_predicted_on_first() {
+    let grammar = grammar! {
+        S -> A;
+        A -> B x;
+        B -> C;
+        C -> c;
+        C -> ;
+    };
+    let b = grammar.symbol("B").unwrap();
+    let c = grammar.symbol("c").unwrap();
+    let x = grammar.symbol("x").unwrap();
+    let table = ParseTable::build(&grammar, grammar.start_symbol());
+
+    // `B -> C` is nullable, but it is also predicted on FIRST(C).
+    let rule = b.rules()[0];
+    assert_eq!(table.get(b, Some(c)), vec![rule]);
+    assert_eq!(table.get(b, Some(x)), vec![rule]);
+}
+
+#[test]
+fn test_first_first_conflict() {
+    let grammar = grammar! {
+        start -> command;
+        command -> write data to   file;
+        command -> write file from data;
+        command -> read  data from file;
+        file -> identifier;
+        data -> identifier;
+    };
+
+    let table = ParseTable::build(&grammar, grammar.start_symbol());
+    let conflicts = table.conflicts();
+    assert_eq!(conflicts.len(), 1);
+
+    let conflict = &conflicts[0];
+    assert_eq!(conflict.nonterminal(), grammar.symbol("command").unwrap());
+    assert_eq!(conflict.lookahead(), grammar.symbol("write"));
+    assert_eq!(conflict.kind(), ConflictKind::FirstFirst);
+    assert_eq!(conflict.rules(), &[rule!(grammar, command -> write data to file), rule!(grammar, command -> write file from data)]);
+    assert_eq!(conflict.witnesses()[0], vec![rule!(grammar, command -> write data to file)]);
+}
+
+#[test]
+fn test_first_first_conflict_witness() {
+    let grammar = grammar! {
+        S -> A;
+        A -> B y;
+        A -> C;
+        B -> ;
+        B -> x;
+        C -> D;
+        D -> x z;
+    };
+
+    let table = ParseTable::build(&grammar, grammar.start_symbol());
+    let conflicts = table.conflicts();
+    assert_eq!(conflicts.len(), 1);
+
+    let conflict = &conflicts[0];
+    assert_eq!(conflict.lookahead(), grammar.symbol("x"));
+    assert_eq!(conflict.kind(), ConflictKind::FirstFirst);
+    assert_eq!(conflict.witnesses()[0], vec![rule!(grammar, A -> B y), rule!(grammar, B -> x)]);
+    assert_eq!(conflict.witnesses()[1], vec![rule!(grammar, A -> C), rule!(grammar, C -> D), rule!(grammar, D -> x z)]);
+}
+
+#[test]
+fn test_first_follow_conflict() {
+    let grammar = grammar! {
+        S -> X ;
+        X -> Y a ;
+        Y -> a ;
+        Y -> ;
+    };
+
+    let table = ParseTable::build(&grammar, grammar.start_symbol());
+    let conflicts = table.conflicts();
+    assert_eq!(conflicts.len(), 1);
+
+    let conflict = &conflicts[0];
+    assert_eq!(conflict.nonterminal(), grammar.symbol("Y").unwrap());
+    assert_eq!(conflict.lookahead(), grammar.symbol("a"));
+    assert_eq!(conflict.kind(), ConflictKind::FirstFollow);
+    assert_eq!(conflict.witnesses()[0], vec![rule!(grammar, Y -> a)]);
+    assert_eq!(conflict.witnesses()[1], vec![rule!(grammar, Y -> ), rule!(grammar, X -> Y a)]);
+}
+
+#[test]
+fn test_eof_conflict() {
+    let grammar = grammar! {
+        S -> X ;
+        X -> Y ;
+        Y -> Z ;
+        Y -> ;
+        Z -> ;
+    };
+
+    let table = ParseTable::build(&grammar, grammar.start_symbol());
+    let conflicts = table.conflicts();
+    assert_eq!(conflicts.len(), 1);
+
+    let conflict = &conflicts[0];
+    assert_eq!(conflict.lookahead(), None);
+    assert_eq!(conflict.kind(), ConflictKind::FirstFollow);
+    assert_eq!(conflict.witnesses()[0], vec![rule!(grammar, Y -> Z), rule!(grammar, Z -> ), rule!(grammar, X -> Y), rule!(grammar, S -> X)]);
+    assert_eq!(conflict.witnesses()[1], vec![rule!(grammar, Y -> ), rule!(grammar, X -> Y), rule!(grammar, S -> X)]);
+}
+
+fn expr_grammar() -> Grammar {
+    grammar! {
+        S -> E;
+        E -> T Emore;
+        Emore -> plus T Emore;
+        Emore -> ;
+        T -> F Tmore ;
+        Tmore -> times F Tmore ;
+        Tmore -> ;
+        F -> id;
+        F -> lparen E rparen;
+    }
+}
+
+#[test]
+fn test_machine_tree() {
+    let grammar = expr_grammar();
+    let table = ParseTable::build(&grammar, grammar.start_symbol());
+
+    let id = grammar.symbol("id").unwrap();
+    let times = grammar.symbol("times").unwrap();
+
+    let mut machine = Machine::new(&table, [id, times, id].into_iter());
+    let tree = machine.run().unwrap();
+    assert_eq!(
+        format!("{tree:?}"),
+        "(S (E (T (F id) (Tmore times (F id) (Tmore))) (Emore)))",
+    );
+    assert_eq!(tree.rule(), Some(grammar.start_rule()));
+    assert_eq!(tree.leaves(), vec![id, times, id]);
+    assert_eq!(tree.rules()[..3], [rule!(grammar, S -> E), rule!(grammar, E -> T Emore), rule!(grammar, T -> F Tmore)]);
+}
+
+#[test]
+fn test_machine_visitor() {
+    struct Recorder(Vec<String>);
+
+    impl<'g> Visitor<'g> for Recorder {
+        fn enter(&mut self, rule: Rule<'g>) {
+            self.0.push(format!("enter {rule:?}"));
+        }
+
+        fn exit(&mut self, rule: Rule<'g>) {
+            self.0.push(format!("exit {:?}", rule.lhs()));
+        }
+
+        fn token(&mut self, symbol: Symbol<'g>) {
+            self.0.push(format!("token {symbol:?}"));
+        }
+    }
+
+    let grammar = expr_grammar();
+    let table = ParseTable::build(&grammar, grammar.symbol("F").unwrap());
+
+    let mut recorder = Recorder(vec![]);
+    let mut machine = Machine::new(&table, [grammar.symbol("id").unwrap()].into_iter());
+    machine.run_with(&mut recorder).unwrap();
+    assert_eq!(recorder.0, vec!["enter F -> id", "token id", "exit F"]);
+}
+
+#[test]
+fn test_machine_errors() {
+    let grammar = expr_grammar();
+    let table = ParseTable::build(&grammar, grammar.start_symbol());
+
+    let id = grammar.symbol("id").unwrap();
+    let plus = grammar.symbol("plus").unwrap();
+    let times = grammar.symbol("times").unwrap();
+    let lparen = grammar.symbol("lparen").unwrap();
+    let rparen = grammar.symbol("rparen").unwrap();
+
+    let mut machine = Machine::new(&table, [id, plus, times].into_iter());
+    assert_eq!(
+        machine.run(),
+        Err(ParseError::UnexpectedToken {
+            position: 2,
+            found: Some(times),
+            expected: [Some(id), Some(lparen)].into_iter().collect(),
+        }),
+    );
+
+    let mut machine = Machine::new(&table, [lparen, id].into_iter());
+    assert_eq!(
+        machine.run(),
+        Err(ParseError::UnexpectedToken {
+            position: 2,
+            found: None,
+            expected: [Some(rparen)].into_iter().collect(),
+        }),
+    );
+
+    let mut machine = Machine::new(&table, [id, rparen].into_iter());
+    assert_eq!(machine.run().unwrap_err().position(), 1);
+}
+
+#[test]
+fn test_machine_conflict() {
+    let grammar = grammar! {
+        S -> X ;
+        X -> Y a ;
+        Y -> a ;
+        Y -> ;
+    };
+
+    let table = ParseTable::build(&grammar, grammar.start_symbol());
+    let a = grammar.symbol("a").unwrap();
+
+    let mut machine = Machine::new(&table, [a, a].into_iter());
+    assert_eq!(
+        machine.run(),
+        Err(ParseError::Conflict {
+            position: 0,
+            nonterminal: grammar.symbol("Y").unwrap(),
+            lookahead: Some(a),
+            rules: vec![rule!(grammar, Y -> a), rule!(grammar, Y -> )],
+        }),
+    );
+}
+
+#[test]
+fn test_recovery_pop() {
+    let grammar = expr_grammar();
+    let table = ParseTable::build(&grammar, grammar.start_symbol());
+    let recovery = Recovery::new(table.analysis(), table.start_symbol());
+
+    let id = grammar.symbol("id").unwrap();
+    let plus = grammar.symbol("plus").unwrap();
+    let t = grammar.symbol("T").unwrap();
+
+    let mut machine = Machine::new(&table, [id, plus, plus, id].into_iter());
+    let diagnostics = machine.run_recovering(&recovery).unwrap_err();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].error().position(), 2);
+    assert_eq!(diagnostics[0].repair(), &Repair::Skip { skipped: vec![], popped: Some(t) });
+}
+
+#[test]
+fn test_recovery_skip() {
+    let grammar = expr_grammar();
+    let table = ParseTable::build(&grammar, grammar.start_symbol());
+    let recovery = Recovery::new(table.analysis(), table.start_symbol());
+
+    let id = grammar.symbol("id").unwrap();
+    let plus = grammar.symbol("plus").unwrap();
+    let times = grammar.symbol("times").unwrap();
+    let tmore = grammar.symbol("Tmore").unwrap();
+
+    let mut machine = Machine::new(&table, [times, id, id, plus, id].into_iter());
+    let diagnostics = machine.run_recovering(&recovery).unwrap_err();
+    assert_eq!(diagnostics.len(), 2);
+    assert_eq!(diagnostics[0].repair(), &Repair::Skip { skipped: vec![times], popped: None });
+    assert_eq!(diagnostics[1].repair(), &Repair::Skip { skipped: vec![id], popped: Some(tmore) });
+}
+
+#[test]
+fn test_recovery_insertion() {
+    let grammar = expr_grammar();
+    let table = ParseTable::build(&grammar, grammar.start_symbol());
+
+    let id = grammar.symbol("id").unwrap();
+    let lparen = grammar.symbol("lparen").unwrap();
+    let rparen = grammar.symbol("rparen").unwrap();
+
+    let recovery = Recovery::new(table.analysis(), table.start_symbol());
+    let mut machine = Machine::new(&table, [lparen, id].into_iter());
+    let diagnostics = machine.run_recovering(&recovery).unwrap_err();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].repair(), &Repair::Insert(rparen));
+
+    let recovery = recovery.insertion(false);
+    let mut machine = Machine::new(&table, [lparen, id].into_iter());
+    let diagnostics = machine.run_recovering(&recovery).unwrap_err();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].repair(), &Repair::Skip { skipped: vec![], popped: Some(rparen) });
+}
+
+#[test]
+fn test_recovery_sync() {
+    let grammar = expr_grammar();
+    let table = ParseTable::build(&grammar, grammar.start_symbol());
+
+    let plus = grammar.symbol("plus").unwrap();
+    let lparen = grammar.symbol("lparen").unwrap();
+    let rparen = grammar.symbol("rparen").unwrap();
+    let e = grammar.symbol("E").unwrap();
+
+    // Without insertion, since inserting `rparen` before `plus` would lead to further errors.
+    let recovery = Recovery::new(table.analysis(), table.start_symbol()).insertion(false);
+    assert!(!recovery.sync_set(e).contains(&Some(plus)));
+
+    let mut machine = Machine::new(&table, [lparen, plus, rparen].into_iter());
+    let diagnostics = machine.run_recovering(&recovery).unwrap_err();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].repair(), &Repair::Skip { skipped: vec![plus], popped: Some(e) });
+
+    let recovery = recovery.sync(e, Some(plus));
+    assert!(recovery.sync_set(e).contains(&Some(plus)));
+
+    let mut machine = Machine::new(&table, [lparen, plus, rparen].into_iter());
+    let diagnostics = machine.run_recovering(&recovery).unwrap_err();
+    assert_eq!(diagnostics.len(), 2);
+    assert_eq!(diagnostics[0].repair(), &Repair::Skip { skipped: vec![], popped: Some(e) });
+    assert_eq!(diagnostics[1].repair(), &Repair::Skip { skipped: vec![plus], popped: None });
+}
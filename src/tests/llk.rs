@@ -0,0 +1,95 @@
+use crate::*;
+use crate::llk::*;
+
+#[test]
+fn test_ll2() {
+    let grammar = grammar! {
+        S -> A;
+        A -> x y;
+        A -> x z;
+    };
+
+    let table = ParseTable::build(&grammar, 1);
+    let conflicts = table.conflicts();
+    assert_eq!(conflicts.len(), 1);
+    assert_eq!(conflicts[0].nonterminal(), grammar.symbol("A").unwrap());
+    assert_eq!(conflicts[0].rules().len(), 2);
+
+    let table = ParseTable::build(&grammar, 2);
+    assert_eq!(table.conflicts().len(), 0);
+
+    let a = grammar.symbol("A").unwrap();
+    let x = grammar.symbol("x");
+    let z = grammar.symbol("z");
+    assert_eq!(table.get(a, &[x, z]), vec![rule!(grammar, A -> x z)]);
+}
+
+#[test]
+fn test_adaptive() {
+    // No fixed k can see past the b's, but a DFA can.
+    let grammar = grammar! {
+        S -> A;
+        A -> B x;
+        A -> B y;
+        B -> b B;
+        B -> ;
+    };
+
+    assert!(!ParseTable::build(&grammar, 3).conflicts().is_empty());
+
+    let table = AdaptiveTable::build(&grammar, 100);
+    assert!(table.unresolved().is_empty());
+
+    let a = grammar.symbol("A").unwrap();
+    let b = grammar.symbol("b");
+    let x = grammar.symbol("x");
+    let y = grammar.symbol("y");
+
+    assert_eq!(table.predict(a, [b, b, b, b, y, None]), Some(rule!(grammar, A -> B y)));
+    assert_eq!(table.predict(a, [x, None]), Some(rule!(grammar, A -> B x)));
+    assert_eq!(table.predict(a, [b, b]), None);
+    assert_eq!(table.predict(a, [None]), None);
+
+    let bb = grammar.symbol("B").unwrap();
+    assert_eq!(table.predict(bb, [b, y]), Some(rule!(grammar, B -> b B)));
+    assert_eq!(table.predict(bb, [y, None]), Some(rule!(grammar, B ->)));
+
+    let decision = table.decision(a).unwrap();
+    assert_eq!(decision.predictions().len(), 2);
+}
+
+#[test]
+fn test_adaptive_unresolved() {
+    let grammar = grammar! {
+        S -> A;
+        A -> x B;
+        A -> x C;
+        B -> y;
+        C -> y;
+    };
+    let table = AdaptiveTable::build(&grammar, 100);
+    let unresolved = &table.unresolved()[0];
+    assert_eq!(unresolved.reason(), UnresolvedReason::Ambiguous);
+    assert_eq!(unresolved.lookahead(), &[grammar.symbol("x")]);
+
+    let grammar = grammar! {
+        S -> A;
+        A -> A x;
+        A -> y;
+    };
+    let table = AdaptiveTable::build(&grammar, 100);
+    assert_eq!(table.unresolved()[0].reason(), UnresolvedReason::LeftRecursive);
+
+    // The lookahead language { b^n c^n x } is not regular.
+    let grammar = grammar! {
+        S -> A;
+        A -> B x;
+        A -> B y;
+        B -> b B c;
+        B -> ;
+    };
+    let table = AdaptiveTable::build(&grammar, 20);
+    let unresolved = &table.unresolved()[0];
+    assert_eq!(unresolved.nonterminal(), grammar.symbol("A").unwrap());
+    assert_eq!(unresolved.reason(), UnresolvedReason::TooManyStates);
+}
@@ -0,0 +1,100 @@
+use crate::*;
+use crate::lrk::*;
+
+#[test]
+fn test_conflicts() {
+    let grammar = grammar! {
+        S -> E;
+        E -> T plus E;
+        E -> T;
+        T -> F times T;
+        T -> F;
+        F -> id;
+        F -> lparen E rparen;
+    };
+
+    let table = ParseTable::build(&grammar, 1);
+    assert_eq!(table.conflicts().len(), 0);
+
+    let table = ParseTable::build(&grammar, 2);
+    assert_eq!(table.conflicts().len(), 0);
+}
+
+#[test]
+fn test_lr2() {
+    let grammar = grammar! {
+        S -> A;
+        A -> B x y;
+        A -> C x z;
+        B -> w;
+        C -> w;
+    };
+
+    assert!(!crate::lr1::ParseTable::build(&grammar).conflicts().is_empty());
+
+    let table = ParseTable::build(&grammar, 1);
+    assert!(!table.conflicts().is_empty());
+
+    let table = ParseTable::build(&grammar, 2);
+    assert_eq!(table.conflicts().len(), 0);
+
+    assert_eq!(minimal_k(&grammar, 3), Some(2));
+    assert_eq!(minimal_k(&grammar, 1), None);
+}
+
+#[test]
+fn test_machine() {
+    let grammar = grammar! {
+        S -> A;
+        A -> B x y;
+        A -> C x z;
+        B -> w;
+        C -> w;
+    };
+
+    let table = ParseTable::build(&grammar, 2);
+    let w = grammar.symbol("w").unwrap();
+    let x = grammar.symbol("x").unwrap();
+    let y = grammar.symbol("y").unwrap();
+    let z = grammar.symbol("z").unwrap();
+
+    assert_eq!(Machine::new(&table, [w, x, y].into_iter()).run(), Ok(()));
+    assert_eq!(Machine::new(&table, [w, x, z].into_iter()).run(), Ok(()));
+
+    let mut machine = Machine::new(&table, [w, x, x].into_iter());
+    assert_eq!(
+        machine.run(),
+        Err(ParseError::UnexpectedToken {
+            position: 1,
+            lookahead: vec![Some(x), Some(x)],
+        }),
+    );
+
+    let mut machine = Machine::new(&table, [w, x].into_iter());
+    let error = machine.run().unwrap_err();
+    assert_eq!(error.position(), 1);
+}
+
+#[test]
+fn test_machine_empty() {
+    let grammar = grammar! {
+        S -> X ;
+        X -> Y b ;
+        Y -> Y a ;
+        Y -> ;
+    };
+
+    let table = ParseTable::build(&grammar, 2);
+    assert_eq!(table.conflicts().len(), 0);
+
+    let input = [
+        grammar.symbol("a").unwrap(),
+        grammar.symbol("a").unwrap(),
+        grammar.symbol("b").unwrap(),
+    ].into_iter();
+    let mut machine = Machine::new(&table, input);
+    assert_eq!(machine.run(), Ok(()));
+
+    let mut machine = Machine::new(&table, [grammar.symbol("a").unwrap()].into_iter());
+    assert!(machine.run().is_err());
+}
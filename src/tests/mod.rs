@@ -1,6 +1,12 @@
 mod macros;
 mod grammar;
 mod ll1;
+mod llk;
 mod lr0;
 mod lr1;
+mod lrk;
 mod virdant;
+mod transform;
+mod generate;
+mod ambiguity;
+mod language;
@@ -0,0 +1,503 @@
+use crate::*;
+
+fn parse<'g>(grammar: &'g Grammar, input: &[&str]) -> ParseTree<'g> {
+    let table = ll1::ParseTable::build(grammar, grammar.start_symbol());
+    assert_eq!(table.conflicts().len(), 0);
+    let input: Vec<Symbol> = input.iter().map(|name| grammar.symbol(name).unwrap()).collect();
+    ll1::Machine::new(&table, input.into_iter()).run().unwrap()
+}
+
+#[test]
+fn test_left_recursion_direct() {
+    let grammar = grammar! {
+        S -> E;
+        E -> E plus T;
+        E -> T;
+        T -> T times F;
+        T -> F;
+        F -> id;
+        F -> lparen E rparen;
+    };
+
+    let transformed = grammar.without_left_recursion();
+    let new_grammar = transformed.grammar();
+    assert_eq!(
+        format!("{new_grammar:?}"),
+        "S -> E\n\
+         E -> T E_tail\n\
+         E_tail -> plus T E_tail\n\
+         E_tail -> \n\
+         T -> F T_tail\n\
+         T_tail -> times F T_tail\n\
+         T_tail -> \n\
+         F -> id\n\
+         F -> lparen E rparen\n",
+    );
+
+    let tree = parse(new_grammar, &["id", "plus", "id", "times", "id", "plus", "id"]);
+    let tree = transformed.rebuild(&tree);
+    assert_eq!(
+        format!("{tree:?}"),
+        "(S (E (E (E (T (F id))) plus (T (T (F id)) times (F id))) plus (T (F id))))",
+    );
+    assert!(std::ptr::eq(tree.rule().unwrap().grammar(), &grammar));
+
+    let tail_rule = rule!(new_grammar, E_tail -> plus T E_tail);
+    assert_eq!(transformed.origins(tail_rule), vec![rule!(grammar, E -> E plus T)]);
+}
+
+#[test]
+fn test_left_recursion_indirect() {
+    let grammar = grammar! {
+        S -> A;
+        A -> B x;
+        A -> y;
+        B -> A z;
+        B -> w;
+    };
+
+    let transformed = grammar.without_left_recursion();
+    let new_grammar = transformed.grammar();
+    assert_eq!(
+        format!("{new_grammar:?}"),
+        "S -> A\n\
+         A -> B x\n\
+         A -> y\n\
+         B -> y z B_tail\n\
+         B -> w B_tail\n\
+         B_tail -> x z B_tail\n\
+         B_tail -> \n",
+    );
+
+    let rule = rule!(new_grammar, B -> y z B_tail);
+    assert_eq!(transformed.origins(rule), vec![rule!(grammar, A -> y), rule!(grammar, B -> A z)]);
+
+    // w x z x
+    let b_tail_end = ParseTree::Node(rule!(new_grammar, B_tail ->), vec![]);
+    let b_tail = ParseTree::Node(
+        rule!(new_grammar, B_tail -> x z B_tail),
+        vec![
+            ParseTree::Token(new_grammar.symbol("x").unwrap()),
+            ParseTree::Token(new_grammar.symbol("z").unwrap()),
+            b_tail_end,
+        ],
+    );
+    let b = ParseTree::Node(
+        rule!(new_grammar, B -> w B_tail),
+        vec![ParseTree::Token(new_grammar.symbol("w").unwrap()), b_tail],
+    );
+    let a = ParseTree::Node(
+        rule!(new_grammar, A -> B x),
+        vec![b, ParseTree::Token(new_grammar.symbol("x").unwrap())],
+    );
+    let tree = ParseTree::Node(rule!(new_grammar, S -> A), vec![a]);
+
+    let tree = transformed.rebuild(&tree);
+    assert_eq!(format!("{tree:?}"), "(S (A (B (A (B w) x) z) x))");
+}
+
+#[test]
+fn test_left_recursion_hidden() {
+    // The rules for C come first, so substituting C exposes X -> X x.
+    let grammar = grammar! {
+        S -> X;
+        C -> c;
+        C -> ;
+        X -> C X x;
+        X -> y;
+    };
+
+    let transformed = grammar.without_left_recursion();
+    let new_grammar = transformed.grammar();
+
+    let x_tail_end = ParseTree::Node(rule!(new_grammar, X_tail ->), vec![]);
+    let x_tail = ParseTree::Node(
+        rule!(new_grammar, X_tail -> x X_tail),
+        vec![ParseTree::Token(new_grammar.symbol("x").unwrap()), x_tail_end],
+    );
+    let x = ParseTree::Node(
+        rule!(new_grammar, X -> y X_tail),
+        vec![ParseTree::Token(new_grammar.symbol("y").unwrap()), x_tail],
+    );
+    let tree = ParseTree::Node(rule!(new_grammar, S -> X), vec![x]);
+
+    let tree = transformed.rebuild(&tree);
+    assert_eq!(format!("{tree:?}"), "(S (X (C) (X y) x))");
+}
+
+#[test]
+fn test_left_factoring() {
+    let grammar = grammar! {
+        start -> command;
+        command -> write data to   file;
+        command -> write file from data;
+        command -> read  data from file;
+        file -> identifier;
+        data -> identifier;
+    };
+
+    let transformed = grammar.left_factored(10);
+    let new_grammar = transformed.grammar();
+    assert_eq!(
+        format!("{new_grammar:?}"),
+        "start -> command\n\
+         command -> write command_factor\n\
+         command -> read data from file\n\
+         command_factor -> data to file\n\
+         command_factor -> file from data\n\
+         file -> identifier\n\
+         data -> identifier\n",
+    );
+
+    let rule = rule!(new_grammar, command_factor -> file from data);
+    assert_eq!(transformed.origins(rule), vec![rule!(grammar, command -> write file from data)]);
+}
+
+#[test]
+fn test_left_factoring_rounds() {
+    let grammar = grammar! {
+        S -> A;
+        A -> x y b;
+        A -> x y c;
+        A -> x z;
+    };
+
+    let transformed = grammar.left_factored(1);
+    assert_eq!(
+        format!("{:?}", transformed.grammar()),
+        "S -> A\n\
+         A -> x A_factor\n\
+         A_factor -> y b\n\
+         A_factor -> y c\n\
+         A_factor -> z\n",
+    );
+
+    let transformed = grammar.left_factored(10);
+    let new_grammar = transformed.grammar();
+    assert_eq!(
+        format!("{new_grammar:?}"),
+        "S -> A\n\
+         A -> x A_factor\n\
+         A_factor -> y A_factor_factor\n\
+         A_factor -> z\n\
+         A_factor_factor -> b\n\
+         A_factor_factor -> c\n",
+    );
+
+    let tree = parse(new_grammar, &["x", "y", "c"]);
+    assert_eq!(format!("{tree:?}"), "(S (A x (A_factor y (A_factor_factor c))))");
+    let tree = transformed.rebuild(&tree);
+    assert_eq!(format!("{tree:?}"), "(S (A x y c))");
+}
+
+#[test]
+fn test_then() {
+    let grammar = grammar! {
+        S -> E;
+        E -> E plus T;
+        E -> E minus T;
+        E -> T;
+        T -> id;
+        T -> id lparen rparen;
+    };
+
+    let transformed = grammar.without_left_recursion().then(|grammar| grammar.left_factored(10));
+    let tree = parse(transformed.grammar(), &["id", "minus", "id", "lparen", "rparen"]);
+    let tree = transformed.rebuild(&tree);
+    assert_eq!(format!("{tree:?}"), "(S (E (E (T id)) minus (T id lparen rparen)))");
+}
+
+#[test]
+fn test_reduced() {
+    let grammar = grammar! {
+        S -> A;
+        A -> x B;
+        A -> y D;
+        B -> B z;
+        D -> d;
+        C -> c;
+    };
+
+    let transformed = grammar.reduced();
+    let new_grammar = transformed.grammar();
+    assert_eq!(
+        format!("{new_grammar:?}"),
+        "S -> A\n\
+         A -> y D\n\
+         D -> d\n",
+    );
+    assert_eq!(new_grammar.symbols().len(), 5);
+
+    let analysis = GrammarAnalysis::build(new_grammar);
+    assert!(analysis.unproductive().is_empty());
+    assert!(analysis.unreachable().is_empty());
+    assert!(analysis.unused_terminals().is_empty());
+
+    let tree = parse(new_grammar, &["y", "d"]);
+    assert_eq!(format!("{:?}", transformed.rebuild(&tree)), "(S (A y (D d)))");
+
+    let grammar = grammar! {
+        S -> A;
+        A -> A x;
+    };
+    assert_eq!(format!("{:?}", grammar.reduced().grammar()), "S -> A\nA -> A\n");
+}
+
+#[test]
+fn test_without_epsilon_rules() {
+    let grammar = grammar! {
+        S -> A;
+        A -> x B y;
+        A -> B;
+        B -> b;
+        B -> ;
+    };
+
+    let transformed = grammar.without_epsilon_rules();
+    let new_grammar = transformed.grammar();
+    assert_eq!(
+        format!("{new_grammar:?}"),
+        "S -> A\n\
+         A -> \n\
+         A -> x B y\n\
+         A -> x y\n\
+         A -> B\n\
+         B -> b\n",
+    );
+
+    // Leaving out B leaves a common prefix behind.
+    let transformed = transformed.then(|grammar| grammar.left_factored(10));
+    let new_grammar = transformed.grammar();
+
+    let tree = parse(new_grammar, &["x", "y"]);
+    assert_eq!(format!("{:?}", transformed.rebuild(&tree)), "(S (A x (B) y))");
+
+    let tree = parse(new_grammar, &[]);
+    assert_eq!(format!("{:?}", transformed.rebuild(&tree)), "(S (A (B)))");
+}
+
+#[test]
+fn test_without_epsilon_rules_nullable_start() {
+    // `A` is nullable, but it also appears inside `A -> a A`, where it must not derive the empty string.
+    let grammar = grammar! {
+        S -> A;
+        A -> a A;
+        A -> ;
+    };
+
+    let transformed = grammar.without_epsilon_rules();
+    let new_grammar = transformed.grammar();
+    assert_eq!(
+        format!("{new_grammar:?}"),
+        "S -> A_opt\n\
+         A_opt -> A\n\
+         A_opt -> \n\
+         A -> a A\n\
+         A -> a\n",
+    );
+
+    // The empty sentence is still parsed through the start rule.
+    let table = lrk::ParseTable::build(new_grammar, 1);
+    assert_eq!(table.conflicts().len(), 0);
+    assert_eq!(lrk::Machine::new(&table, [].into_iter()).run(), Ok(()));
+    let a = new_grammar.symbol("a").unwrap();
+    assert_eq!(lrk::Machine::new(&table, [a, a].into_iter()).run(), Ok(()));
+
+    let tree = parse_exhaustive(new_grammar, &[]);
+    assert_eq!(format!("{:?}", transformed.rebuild(&tree)), "(S (A))");
+    let tree = parse_exhaustive(new_grammar, &["a"]);
+    assert_eq!(format!("{:?}", transformed.rebuild(&tree)), "(S (A a (A)))");
+}
+
+#[test]
+fn test_without_epsilon_rules_drops_empty_nonterminals() {
+    let grammar = grammar! {
+        S -> A;
+        A -> x B;
+        B -> ;
+    };
+
+    let transformed = grammar.without_epsilon_rules();
+    let new_grammar = transformed.grammar();
+    assert_eq!(format!("{new_grammar:?}"), "S -> A\nA -> x\n");
+    assert_eq!(new_grammar.symbol("B"), None);
+    assert_eq!(new_grammar.terminals(), vec![new_grammar.symbol("x").unwrap()]);
+}
+
+#[test]
+fn test_without_epsilon_rules_empty_language() {
+    let grammar = grammar! {
+        S -> A;
+        A -> A;
+    };
+
+    let transformed = grammar.without_epsilon_rules();
+    assert_eq!(format!("{:?}", transformed.grammar()), "S -> A\nA -> A\n");
+}
+
+#[test]
+fn test_without_unit_rules() {
+    let grammar = grammar! {
+        S -> Item;
+        Item -> ModDef;
+        Item -> Stmt;
+        ModDef -> mod id;
+        Stmt -> Expr;
+        Stmt -> let id;
+        Expr -> id;
+    };
+
+    let transformed = grammar.without_unit_rules();
+    let new_grammar = transformed.grammar();
+    assert_eq!(
+        format!("{new_grammar:?}"),
+        "S -> Item\n\
+         Item -> mod id\n\
+         Item -> let id\n\
+         Item -> id\n\
+         ModDef -> mod id\n\
+         Stmt -> let id\n\
+         Stmt -> id\n\
+         Expr -> id\n",
+    );
+
+    let rule = rule!(new_grammar, Item -> id);
+    assert_eq!(
+        transformed.origins(rule),
+        vec![rule!(grammar, Item -> Stmt), rule!(grammar, Stmt -> Expr), rule!(grammar, Expr -> id)],
+    );
+
+    let tree = parse(new_grammar, &["id"]);
+    assert_eq!(format!("{:?}", transformed.rebuild(&tree)), "(S (Item (Stmt (Expr id))))");
+}
+
+// Find a parse tree by trying every way of splitting the input.
+// Every symbol must derive at least one token, apart from rules with an empty RHS.
+fn parse_exhaustive<'g>(grammar: &'g Grammar, input: &[&str]) -> ParseTree<'g> {
+    let input: Vec<Symbol> = input.iter().map(|name| grammar.symbol(name).unwrap()).collect();
+    derive(&input, grammar.start_symbol()).unwrap()
+}
+
+fn derive<'g>(input: &[Symbol<'g>], symbol: Symbol<'g>) -> Option<ParseTree<'g>> {
+    if symbol.is_terminal() {
+        return (input == [symbol]).then_some(ParseTree::Token(symbol));
+    }
+    symbol.rules().into_iter().find_map(|rule| {
+        let children = derive_seq(input, &rule.rhs())?;
+        Some(ParseTree::Node(rule, children))
+    })
+}
+
+fn derive_seq<'g>(input: &[Symbol<'g>], seq: &[Symbol<'g>]) -> Option<Vec<ParseTree<'g>>> {
+    match seq {
+        [] => input.is_empty().then(Vec::new),
+        [symbol] => Some(vec![derive(input, *symbol)?]),
+        [first, rest @ ..] => (1..input.len()).find_map(|split| {
+            let tree = derive(&input[..split], *first)?;
+            let mut trees = derive_seq(&input[split..], rest)?;
+            trees.insert(0, tree);
+            Some(trees)
+        }),
+    }
+}
+
+fn normal_form_grammar() -> Grammar {
+    grammar! {
+        S -> E;
+        E -> E plus T;
+        E -> T;
+        T -> id;
+        T -> lparen E rparen;
+        T -> lparen rparen;
+    }
+}
+
+#[test]
+fn test_cnf() {
+    let grammar = normal_form_grammar();
+    let transformed = grammar.to_cnf();
+    let new_grammar = transformed.grammar();
+
+    for rule in new_grammar.rules().into_iter().skip(1) {
+        match rule.rhs().as_slice() {
+            [a] => assert!(a.is_terminal(), "{rule:?}"),
+            [b, c] => assert!(b.is_nonterminal() && c.is_nonterminal(), "{rule:?}"),
+            _ => panic!("Not in CNF: {rule:?}"),
+        }
+    }
+
+    let tree = parse_exhaustive(new_grammar, &["id", "plus", "lparen", "id", "rparen"]);
+    assert_eq!(
+        format!("{:?}", transformed.rebuild(&tree)),
+        "(S (E (E (T id)) plus (T lparen (E (T id)) rparen)))",
+    );
+
+    let grammar = grammar! {
+        S -> A;
+        A -> a A;
+        A -> ;
+    };
+    let transformed = grammar.to_cnf();
+    let new_grammar = transformed.grammar();
+    assert_eq!(
+        format!("{new_grammar:?}"),
+        "S -> A_opt\n\
+         A_opt -> \n\
+         A_opt -> a_term A\n\
+         A_opt -> a\n\
+         A -> a_term A\n\
+         A -> a\n\
+         a_term -> a\n",
+    );
+    let tree = parse_exhaustive(new_grammar, &[]);
+    assert_eq!(format!("{:?}", transformed.rebuild(&tree)), "(S (A))");
+    let tree = parse_exhaustive(new_grammar, &["a", "a"]);
+    assert_eq!(format!("{:?}", transformed.rebuild(&tree)), "(S (A a (A a (A))))");
+}
+
+#[test]
+fn test_gnf() {
+    let grammar = normal_form_grammar();
+    let transformed = grammar.to_gnf();
+    let new_grammar = transformed.grammar();
+
+    for rule in new_grammar.rules().into_iter().skip(1) {
+        let rhs = rule.rhs();
+        assert!(rhs[0].is_terminal(), "{rule:?}");
+        assert!(rhs[1..].iter().all(|symbol| symbol.is_nonterminal()), "{rule:?}");
+    }
+
+    let tree = parse_exhaustive(new_grammar, &["id", "plus", "lparen", "id", "plus", "id", "rparen", "plus", "id"]);
+    assert_eq!(
+        format!("{:?}", transformed.rebuild(&tree)),
+        "(S (E (E (E (T id)) plus (T lparen (E (E (T id)) plus (T id)) rparen)) plus (T id)))",
+    );
+}
+
+#[test]
+fn test_gnf_empty_sentence() {
+    let grammar = grammar! {
+        S -> A;
+        A -> a A b;
+        A -> ;
+    };
+    let transformed = grammar.to_gnf();
+    let new_grammar = transformed.grammar();
+
+    // Only the symbol on the RHS of the start rule derives the empty string, and it appears on no RHS.
+    let start_rhs = new_grammar.start_rule().rhs()[0];
+    for rule in new_grammar.rules().into_iter().skip(1) {
+        let rhs = rule.rhs();
+        if rhs.is_empty() {
+            assert_eq!(rule.lhs(), start_rhs);
+            continue;
+        }
+        assert!(rhs[0].is_terminal(), "{rule:?}");
+        assert!(rhs[1..].iter().all(|symbol| symbol.is_nonterminal() && *symbol != start_rhs), "{rule:?}");
+    }
+
+    let tree = parse_exhaustive(new_grammar, &[]);
+    assert_eq!(format!("{:?}", transformed.rebuild(&tree)), "(S (A))");
+    let tree = parse_exhaustive(new_grammar, &["a", "a", "b", "b"]);
+    assert_eq!(format!("{:?}", transformed.rebuild(&tree)), "(S (A a (A a (A) b) b))");
+}
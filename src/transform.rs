@@ -0,0 +1,378 @@
+mod left_recursion;
+mod left_factoring;
+mod reduce;
+mod epsilon;
+mod unit_rules;
+mod normal_form;
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::*;
+
+/// A `Grammar` which was produced from another by a sequence of transformations.
+///
+/// Each transformation is recorded as a stage.
+/// For every rule in a stage, the stage remembers how to derive the same string
+/// using the rules of the stage before it.
+/// This lets a parse tree for the transformed grammar
+/// be rebuilt into a parse tree for the original one.
+pub struct Transformed<'g> {
+    original: &'g Grammar,
+    stages: Vec<Stage>,
+}
+
+struct Stage {
+    grammar: Grammar,
+    // One for each rule of `grammar`.
+    provenance: Vec<Provenance>,
+}
+
+/// How to rebuild an application of a new rule in terms of the rules of the previous grammar.
+///
+/// Rebuilding walks the new parse tree from left to right,
+/// pushing each terminal onto a stack as it is reached,
+/// as in an LR parser.
+/// Each reduction applies a rule of the previous grammar
+/// to the items on the top of the stack.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Provenance {
+    reductions: Vec<Reduction>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Reduction {
+    // How many children of the new rule come before this reduction.
+    position: usize,
+    rule: RuleIndex,
+    // How many items on the top of the stack this reduction reaches underneath.
+    // This is nonzero only when a rule was made to start after a subtree which is already on the stack.
+    depth: usize,
+}
+
+// An alternative for a nonterminal, written in terms of symbol names.
+#[derive(Clone)]
+struct Alternative {
+    rhs: Vec<String>,
+    provenance: Provenance,
+}
+
+// A parse tree which isn't tied to the lifetime of a `Grammar`.
+enum RawTree {
+    Token(String),
+    Node(RuleIndex, Vec<RawTree>),
+}
+
+impl<'g> Transformed<'g> {
+    /// The grammar before any transformations.
+    pub fn original(&self) -> &'g Grammar {
+        self.original
+    }
+
+    /// The grammar after all of the transformations.
+    pub fn grammar(&self) -> &Grammar {
+        match self.stages.last() {
+            Some(stage) => &stage.grammar,
+            None => self.original,
+        }
+    }
+
+    /// Apply a further transformation to the result.
+    ///
+    /// Rebuilding goes back through both transformations to the original grammar.
+    pub fn then<F>(mut self, transform: F) -> Transformed<'g>
+    where
+        F: for<'t> FnOnce(&'t Grammar) -> Transformed<'t>,
+    {
+        let stages = transform(self.grammar()).stages;
+        self.stages.extend(stages);
+        self
+    }
+
+    /// The rules of the original grammar which `rule` was built from.
+    pub fn origins(&self, rule: Rule<'_>) -> Vec<Rule<'g>> {
+        assert!(std::ptr::eq(rule.grammar(), self.grammar()), "Rule belongs to a different grammar: {rule:?}");
+
+        let mut rules = BTreeSet::from([rule.index()]);
+        for stage in self.stages.iter().rev() {
+            rules = rules
+                .into_iter()
+                .flat_map(|rule| stage.provenance[usize::from(rule)].rules())
+                .collect();
+        }
+
+        let original_rules = self.original.rules();
+        rules.into_iter().map(|rule| original_rules[usize::from(rule)]).collect()
+    }
+
+    /// Rebuild a parse tree for the transformed grammar into a parse tree for the original grammar.
+    ///
+    /// Both trees have the same leaves.
+    pub fn rebuild(&self, tree: &ParseTree<'_>) -> ParseTree<'g> {
+        let mut tree = RawTree::from_parse_tree(tree);
+        for (i, stage) in self.stages.iter().enumerate().rev() {
+            let previous = if i == 0 {
+                self.original
+            } else {
+                &self.stages[i - 1].grammar
+            };
+            tree = stage.rebuild(&tree, previous);
+        }
+        tree.to_parse_tree(self.original)
+    }
+
+    pub(crate) fn new(original: &'g Grammar, grammar: Grammar, provenance: Vec<Provenance>) -> Transformed<'g> {
+        assert_eq!(grammar.rules().len(), provenance.len());
+        Transformed {
+            original,
+            stages: vec![Stage { grammar, provenance }],
+        }
+    }
+}
+
+impl Stage {
+    fn rebuild(&self, tree: &RawTree, previous: &Grammar) -> RawTree {
+        let mut events = vec![];
+        self.replay(tree, &mut events);
+
+        let previous_rules = previous.rules();
+        let mut stack = vec![];
+        for event in events {
+            match event {
+                Event::Shift(name) => stack.push(RawTree::Token(name)),
+                Event::Reduce(reduction) => {
+                    let arity = previous_rules[usize::from(reduction.rule)].rhs().len();
+                    let top = stack.len() - reduction.depth;
+                    let children: Vec<RawTree> = stack.drain(top - arity..top).collect();
+                    stack.insert(top - arity, RawTree::Node(reduction.rule, children));
+                }
+            }
+        }
+        assert_eq!(stack.len(), 1, "Provenance did not rebuild a single tree");
+        stack.pop().unwrap()
+    }
+
+    // Walk the tree from left to right, interleaving the tokens with the reductions.
+    fn replay(&self, tree: &RawTree, events: &mut Vec<Event>) {
+        match tree {
+            RawTree::Token(name) => events.push(Event::Shift(name.clone())),
+            RawTree::Node(rule, children) => {
+                let reductions = &self.provenance[usize::from(*rule)].reductions;
+                let mut reductions = reductions.iter().peekable();
+                for position in 0..=children.len() {
+                    while let Some(reduction) = reductions.next_if(|reduction| reduction.position == position) {
+                        events.push(Event::Reduce(*reduction));
+                    }
+                    if let Some(child) = children.get(position) {
+                        self.replay(child, events);
+                    }
+                }
+            }
+        }
+    }
+}
+
+enum Event {
+    Shift(String),
+    Reduce(Reduction),
+}
+
+impl Provenance {
+    /// The new rule is the same as `rule`.
+    pub(crate) fn of(rule: Rule<'_>) -> Provenance {
+        Provenance {
+            reductions: vec![Reduction {
+                position: rule.rhs().len(),
+                rule: rule.index(),
+                depth: 0,
+            }],
+        }
+    }
+
+    /// The new rule doesn't correspond to any node in the previous grammar.
+    /// Its children are passed through to its parent.
+    pub(crate) fn none() -> Provenance {
+        Provenance::default()
+    }
+
+    // The rules of the previous grammar used by the reductions.
+    fn rules(&self) -> Vec<RuleIndex> {
+        self.reductions.iter().map(|reduction| reduction.rule).collect()
+    }
+
+    /// The provenance of a rule after the symbol at `position` in its RHS
+    /// is replaced by the RHS of a rule with provenance `inner` and length `inner_len`.
+    pub(crate) fn substitute(&self, position: usize, inner: &Provenance, inner_len: usize) -> Provenance {
+        let mut reductions = vec![];
+        reductions.extend(self.reductions.iter().filter(|reduction| reduction.position <= position).copied());
+        reductions.extend(inner.reductions.iter().map(|reduction| Reduction {
+            position: reduction.position + position,
+            ..*reduction
+        }));
+        reductions.extend(self.reductions.iter().filter(|reduction| reduction.position > position).map(|reduction| Reduction {
+            position: reduction.position + inner_len - 1,
+            ..*reduction
+        }));
+        Provenance { reductions }
+    }
+
+    /// The provenance of a rule after the first `len` symbols of its RHS are removed,
+    /// on the understanding that the subtrees for those symbols are already on the stack when the rule begins.
+    pub(crate) fn without_prefix(&self, len: usize) -> Provenance {
+        let reductions = self.reductions.iter().map(|reduction| {
+            if reduction.position <= len {
+                Reduction {
+                    position: 0,
+                    depth: reduction.depth + len - reduction.position,
+                    ..*reduction
+                }
+            } else {
+                Reduction {
+                    position: reduction.position - len,
+                    ..*reduction
+                }
+            }
+        });
+        Provenance {
+            reductions: reductions.collect(),
+        }
+    }
+}
+
+impl RawTree {
+    fn from_parse_tree(tree: &ParseTree<'_>) -> RawTree {
+        match tree {
+            ParseTree::Token(symbol) => RawTree::Token(symbol.name()),
+            ParseTree::Node(rule, children) => {
+                RawTree::Node(rule.index(), children.iter().map(RawTree::from_parse_tree).collect())
+            }
+        }
+    }
+
+    fn to_parse_tree<'g>(&self, grammar: &'g Grammar) -> ParseTree<'g> {
+        match self {
+            RawTree::Token(name) => {
+                ParseTree::Token(grammar.symbol(name).unwrap_or_else(|| panic!("No such symbol: {name}")))
+            }
+            RawTree::Node(rule, children) => {
+                let rule = grammar.rules()[usize::from(*rule)];
+                ParseTree::Node(rule, children.iter().map(|child| child.to_parse_tree(grammar)).collect())
+            }
+        }
+    }
+}
+
+/// A grammar under construction by a transformation, or by an operation which combines grammars.
+///
+/// Rules are given by symbol names, together with their provenance.
+/// Symbols are declared in the order they are first mentioned,
+/// and only the symbols which some rule uses are declared.
+pub(crate) struct Draft {
+    // Every name which is taken, including names which no rule uses.
+    symbols: Vec<String>,
+    rules: Vec<(String, Vec<String>, Provenance)>,
+}
+
+impl Draft {
+    /// Start a draft which mentions every symbol of `grammar`, in the same order.
+    ///
+    /// The names stay taken even if no rule uses them.
+    pub(crate) fn new(grammar: &Grammar) -> Draft {
+        Draft {
+            symbols: grammar.symbols().iter().map(|symbol| symbol.name()).collect(),
+            rules: vec![],
+        }
+    }
+
+    /// Start a draft in which no names are taken.
+    pub(crate) fn empty() -> Draft {
+        Draft {
+            symbols: vec![],
+            rules: vec![],
+        }
+    }
+
+    /// Mention the symbol `name`, so that the name is taken.
+    pub(crate) fn mention(&mut self, name: &str) {
+        if !self.symbols.iter().any(|symbol| symbol == name) {
+            self.symbols.push(name.to_string());
+        }
+    }
+
+    /// Declare a new symbol with a name based on `base`.
+    ///
+    /// The name is `{base}_{suffix}`, followed by a number if that name is already taken.
+    pub(crate) fn fresh(&mut self, base: &str, suffix: &str) -> String {
+        self.unused(&format!("{base}_{suffix}"))
+    }
+
+    /// Declare a new symbol called `name`, followed by a number if that name is already taken.
+    pub(crate) fn unused(&mut self, name: &str) -> String {
+        let mut unused = name.to_string();
+        let mut n = 2;
+        while self.symbols.contains(&unused) {
+            unused = format!("{name}{n}");
+            n += 1;
+        }
+        self.symbols.push(unused.clone());
+        unused
+    }
+
+    /// Declare a new symbol called `name`, adding `prefix` to the front until the name isn't taken.
+    pub(crate) fn prefixed(&mut self, prefix: &str, name: &str) -> String {
+        assert!(!prefix.is_empty());
+        let mut prefixed = name.to_string();
+        while self.symbols.contains(&prefixed) {
+            prefixed = format!("{prefix}{prefixed}");
+        }
+        self.symbols.push(prefixed.clone());
+        prefixed
+    }
+
+    // The rules of `grammar`, grouped by LHS.
+    // The nonterminals are in the order their rules first appear.
+    fn alternatives(grammar: &Grammar) -> (Vec<String>, BTreeMap<String, Vec<Alternative>>) {
+        let mut order = vec![];
+        let mut alternatives: BTreeMap<String, Vec<Alternative>> = BTreeMap::new();
+        for rule in grammar.rules() {
+            let name = rule.lhs().name();
+            if !alternatives.contains_key(&name) {
+                order.push(name.clone());
+            }
+            alternatives.entry(name).or_default().push(Alternative {
+                rhs: rule.rhs().iter().map(|symbol| symbol.name()).collect(),
+                provenance: Provenance::of(rule),
+            });
+        }
+        (order, alternatives)
+    }
+
+    fn alternative(&mut self, lhs: &str, alternative: &Alternative) {
+        self.rule(lhs.to_string(), alternative.rhs.clone(), alternative.provenance.clone());
+    }
+
+    /// Add a rule. The first rule added is the start rule.
+    pub(crate) fn rule(&mut self, lhs: String, rhs: Vec<String>, provenance: Provenance) {
+        for name in std::iter::once(&lhs).chain(&rhs) {
+            self.mention(name);
+        }
+        self.rules.push((lhs, rhs, provenance));
+    }
+
+    pub(crate) fn build(self, original: &Grammar) -> Transformed<'_> {
+        let provenance = self.rules.iter().map(|(_lhs, _rhs, provenance)| provenance.clone()).collect();
+        Transformed::new(original, self.grammar(), provenance)
+    }
+
+    /// Build the grammar on its own, ignoring the provenance of its rules.
+    pub(crate) fn grammar(self) -> Grammar {
+        let used: BTreeSet<&String> = self.rules.iter().flat_map(|(lhs, rhs, _provenance)| std::iter::once(lhs).chain(rhs)).collect();
+        let mut builder = Grammar::new();
+        for symbol in self.symbols.iter().filter(|symbol| used.contains(symbol)) {
+            builder = builder.symbol(symbol.as_str());
+        }
+        for (lhs, rhs, _provenance) in &self.rules {
+            builder = builder.rule(lhs.as_str(), &rhs.iter().map(String::as_str).collect::<Vec<_>>());
+        }
+        builder.build()
+    }
+}
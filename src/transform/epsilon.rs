@@ -0,0 +1,142 @@
+use std::collections::BTreeMap;
+
+use super::*;
+
+impl Grammar {
+    /// Remove the rules with an empty RHS without changing the language.
+    ///
+    /// Each rule is replaced by one copy for every way of leaving out the nullable symbols on its RHS,
+    /// except the copy with an empty RHS.
+    /// Copies of the form `A -> A`, and copies which are the same as an earlier one, are dropped.
+    /// A nonterminal which can only derive the empty string is left out everywhere.
+    ///
+    /// The start rule `S -> X` is kept as the only rule for `S`.
+    /// If `X` is nullable, it keeps a single rule with an empty RHS.
+    /// When `X` also appears on the RHS of another rule, where it must not derive the empty string,
+    /// the start rule becomes `S -> X_opt` instead, with the rules `X_opt -> X` and `X_opt ->`.
+    /// When `X` can't derive any string, it is given the single rule `X -> X`, as in `reduced`.
+    pub fn without_epsilon_rules(&self) -> Transformed<'_> {
+        let analysis = GrammarAnalysis::build(self);
+        let start_symbol = self.start_symbol();
+        let nulls = null_derivations(self, &analysis);
+
+        // The nonterminals which derive some nonempty string.
+        // The rest are left out wherever they appear.
+        let mut nonempty = BTreeSet::new();
+        loop {
+            let mut dirty = false;
+            for rule in self.rules() {
+                if !nonempty.contains(&rule.lhs()) &&
+                    rule.rhs().iter().any(|symbol| symbol.is_terminal() || nonempty.contains(symbol)) {
+                    nonempty.insert(rule.lhs());
+                    dirty = true;
+                }
+            }
+            if !dirty {
+                break;
+            }
+        }
+
+        let mut draft = Draft::new(self);
+        for rule in self.rules() {
+            if rule.is_start_rule() {
+                let x = rule.rhs()[0];
+                if x.is_nonterminal() && !nonempty.contains(&x) {
+                    draft.rule(start_symbol.name(), vec![x.name()], Provenance::of(rule));
+                    if analysis.is_nullable(x) {
+                        draft.rule(x.name(), vec![], nulls[&x].clone());
+                    } else {
+                        draft.rule(x.name(), vec![x.name()], Provenance::none());
+                    }
+                } else if analysis.is_nullable(x) && appears_in_rhs(self, x) {
+                    // `X` can't be given an empty RHS without changing the language elsewhere.
+                    let optional = draft.fresh(&x.name(), "opt");
+                    draft.rule(start_symbol.name(), vec![optional.clone()], Provenance::of(rule));
+                    draft.rule(optional.clone(), vec![x.name()], Provenance::none());
+                    draft.rule(optional, vec![], nulls[&x].clone());
+                } else if analysis.is_nullable(x) {
+                    draft.rule(start_symbol.name(), vec![x.name()], Provenance::of(rule));
+                    draft.rule(x.name(), vec![], nulls[&x].clone());
+                } else {
+                    draft.rule(start_symbol.name(), vec![x.name()], Provenance::of(rule));
+                }
+                continue;
+            }
+
+            let mut variants: Vec<Alternative> = vec![Alternative {
+                rhs: vec![],
+                provenance: Provenance::of(rule),
+            }];
+
+            // Decide whether to keep each symbol, from right to left,
+            // so that the positions of the symbols still to be decided are unaffected.
+            let rhs = rule.rhs();
+            for (position, symbol) in rhs.iter().enumerate().rev() {
+                let keep = symbol.is_terminal() || nonempty.contains(symbol);
+                let omit = analysis.is_nullable(*symbol);
+
+                let mut next_variants = vec![];
+                for variant in variants {
+                    if keep {
+                        let mut rhs = vec![symbol.name()];
+                        rhs.extend(variant.rhs.iter().cloned());
+                        next_variants.push(Alternative {
+                            rhs,
+                            provenance: variant.provenance.clone(),
+                        });
+                    }
+                    if omit {
+                        next_variants.push(Alternative {
+                            rhs: variant.rhs,
+                            provenance: variant.provenance.substitute(position, &nulls[symbol], 0),
+                        });
+                    }
+                }
+                variants = next_variants;
+            }
+
+            let lhs = rule.lhs().name();
+            let mut seen = vec![];
+            for variant in variants {
+                if variant.rhs.is_empty() || variant.rhs == [lhs.clone()] || seen.contains(&variant.rhs) {
+                    continue;
+                }
+                seen.push(variant.rhs.clone());
+                draft.alternative(&lhs, &variant);
+            }
+        }
+
+        draft.build(self)
+    }
+}
+
+// Does `symbol` appear on the RHS of any rule apart from the start rule?
+fn appears_in_rhs<'g>(grammar: &'g Grammar, symbol: Symbol<'g>) -> bool {
+    grammar.rules().iter().any(|rule| !rule.is_start_rule() && rule.rhs().contains(&symbol))
+}
+
+// For each nullable nonterminal, the reductions which derive the empty string from it.
+fn null_derivations<'g>(grammar: &'g Grammar, analysis: &GrammarAnalysis<'g>) -> BTreeMap<Symbol<'g>, Provenance> {
+    let mut nulls: BTreeMap<Symbol<'g>, Provenance> = BTreeMap::new();
+    loop {
+        let mut dirty = false;
+        for rule in grammar.rules() {
+            let lhs = rule.lhs();
+            if analysis.is_nullable(lhs) &&
+                !nulls.contains_key(&lhs) &&
+                rule.rhs().iter().all(|symbol| nulls.contains_key(symbol)) {
+                // Each symbol on the RHS is replaced by its derivation of the empty string.
+                let mut provenance = Provenance::of(rule);
+                for (position, symbol) in rule.rhs().iter().enumerate().rev() {
+                    provenance = provenance.substitute(position, &nulls[symbol], 0);
+                }
+                nulls.insert(lhs, provenance);
+                dirty = true;
+            }
+        }
+        if !dirty {
+            break;
+        }
+    }
+    nulls
+}
@@ -0,0 +1,98 @@
+use super::*;
+
+impl Grammar {
+    /// Factor out the prefixes shared between the rules for each nonterminal.
+    ///
+    /// The rules `A -> π α` and `A -> π β` with the longest common prefix `π`
+    /// are replaced by `A -> π A_factor`, `A_factor -> α` and `A_factor -> β`.
+    /// This is repeated on the new rules until no two rules for the same nonterminal begin with the same symbol,
+    /// or until `max_rounds` rounds have been made.
+    ///
+    /// The rules for the start symbol are left as they are.
+    pub fn left_factored(&self, max_rounds: usize) -> Transformed<'_> {
+        let start_symbol = self.start_symbol().name();
+        let (mut order, mut alternatives) = Draft::alternatives(self);
+        let mut draft = Draft::new(self);
+
+        for _round in 0..max_rounds {
+            let mut changed = false;
+            let mut next_order = vec![];
+
+            for name in order {
+                next_order.push(name.clone());
+                if name == start_symbol {
+                    continue;
+                }
+
+                let mut factored: Vec<Alternative> = vec![];
+                let mut factors = vec![];
+                for group in group_by_first(&alternatives[&name]) {
+                    if group.len() == 1 {
+                        factored.push(group[0].clone());
+                        continue;
+                    }
+
+                    let prefix = common_prefix(&group);
+                    let factor = draft.fresh(&name, "factor");
+
+                    let mut rhs = prefix.to_vec();
+                    rhs.push(factor.clone());
+                    factored.push(Alternative {
+                        rhs,
+                        provenance: Provenance::none(),
+                    });
+
+                    let rests = group
+                        .iter()
+                        .map(|alternative| Alternative {
+                            rhs: alternative.rhs[prefix.len()..].to_vec(),
+                            provenance: alternative.provenance.without_prefix(prefix.len()),
+                        })
+                        .collect();
+                    alternatives.insert(factor.clone(), rests);
+                    factors.push(factor);
+                }
+
+                changed |= !factors.is_empty();
+                alternatives.insert(name, factored);
+                next_order.extend(factors);
+            }
+
+            order = next_order;
+            if !changed {
+                break;
+            }
+        }
+
+        for name in &order {
+            for alternative in &alternatives[name] {
+                draft.alternative(name, alternative);
+            }
+        }
+        draft.build(self)
+    }
+}
+
+// Group the alternatives by their first symbol, in the order the groups first appear.
+// Empty alternatives are each in a group of their own.
+fn group_by_first(alternatives: &[Alternative]) -> Vec<Vec<Alternative>> {
+    let mut groups: Vec<Vec<Alternative>> = vec![];
+    for alternative in alternatives {
+        let first = alternative.rhs.first();
+        let group = groups.iter_mut().find(|group| first.is_some() && group[0].rhs.first() == first);
+        match group {
+            Some(group) => group.push(alternative.clone()),
+            None => groups.push(vec![alternative.clone()]),
+        }
+    }
+    groups
+}
+
+fn common_prefix(group: &[Alternative]) -> &[String] {
+    let mut prefix: &[String] = &group[0].rhs;
+    for alternative in &group[1..] {
+        let len = prefix.iter().zip(&alternative.rhs).take_while(|(a, b)| a == b).count();
+        prefix = &prefix[..len];
+    }
+    prefix
+}
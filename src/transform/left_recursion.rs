@@ -0,0 +1,138 @@
+use std::collections::BTreeMap;
+
+use super::*;
+
+impl Grammar {
+    /// Remove direct and indirect left recursion using Paull's algorithm.
+    ///
+    /// For each left-recursive nonterminal `A`, the rules `A -> A α` and `A -> β`
+    /// are replaced by `A -> β A_tail`, `A_tail -> α A_tail` and `A_tail ->`.
+    /// Rules of the form `A -> A` are dropped, since they only add ambiguity.
+    /// Nonterminals are processed in the order their rules appear,
+    /// and one is only substituted into the rules of a later one when that could expose left recursion.
+    ///
+    /// Left recursion hidden behind a nullable symbol (as in `A -> B A x` with `B` nullable)
+    /// is only removed when the nullable symbol's rules come first.
+    /// Nonterminals which can't derive any string of terminals are left as they are.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start symbol is left-recursive.
+    pub fn without_left_recursion(&self) -> Transformed<'_> {
+        let start_symbol = self.start_symbol();
+
+        // The tails are added to the alternatives as they are made.
+        let (order, mut alternatives) = Draft::alternatives(self);
+
+        let unproductive: BTreeSet<String> = GrammarAnalysis::build(self).unproductive().iter().map(|symbol| symbol.name()).collect();
+
+        let mut draft = Draft::new(self);
+        let mut tails: BTreeMap<String, String> = BTreeMap::new();
+
+        for (i, name) in order.iter().enumerate() {
+            if unproductive.contains(name) {
+                continue;
+            }
+
+            // Substitute earlier nonterminals on the left of the rules for this one.
+            for earlier in &order[..i] {
+                let nullable = alternatives[earlier].iter().any(|alternative| alternative.rhs.is_empty());
+                if !nullable && !left_reaches(&alternatives, earlier, name) {
+                    continue;
+                }
+
+                let earlier_alternatives = alternatives[earlier].clone();
+                let mut substituted = vec![];
+                for alternative in &alternatives[name] {
+                    if alternative.rhs.first() == Some(earlier) {
+                        for inner in &earlier_alternatives {
+                            let mut rhs = inner.rhs.clone();
+                            rhs.extend(alternative.rhs[1..].iter().cloned());
+                            substituted.push(Alternative {
+                                rhs,
+                                provenance: alternative.provenance.substitute(0, &inner.provenance, inner.rhs.len()),
+                            });
+                        }
+                    } else {
+                        substituted.push(alternative.clone());
+                    }
+                }
+                alternatives.insert(name.clone(), substituted);
+            }
+
+            // Eliminate the direct left recursion.
+            let (recursive, others): (Vec<Alternative>, Vec<Alternative>) = alternatives[name]
+                .iter()
+                .filter(|alternative| alternative.rhs != [name.clone()])
+                .cloned()
+                .partition(|alternative| alternative.rhs.first() == Some(name));
+
+            if recursive.is_empty() {
+                alternatives.insert(name.clone(), others);
+                continue;
+            }
+            assert!(*name != start_symbol.name(), "The start symbol must not be left-recursive");
+
+            let tail = draft.fresh(name, "tail");
+            let others = others
+                .into_iter()
+                .map(|mut alternative| {
+                    alternative.rhs.push(tail.clone());
+                    alternative
+                })
+                .collect();
+            alternatives.insert(name.clone(), others);
+
+            let mut tail_alternatives: Vec<Alternative> = recursive
+                .into_iter()
+                .map(|alternative| {
+                    let mut rhs = alternative.rhs[1..].to_vec();
+                    rhs.push(tail.clone());
+                    Alternative {
+                        rhs,
+                        provenance: alternative.provenance.without_prefix(1),
+                    }
+                })
+                .collect();
+            tail_alternatives.push(Alternative {
+                rhs: vec![],
+                provenance: Provenance::none(),
+            });
+            alternatives.insert(tail.clone(), tail_alternatives);
+            tails.insert(name.clone(), tail);
+        }
+
+        for name in &order {
+            for alternative in &alternatives[name] {
+                draft.alternative(name, alternative);
+            }
+            if let Some(tail) = tails.get(name) {
+                for alternative in &alternatives[tail] {
+                    draft.alternative(tail, alternative);
+                }
+            }
+        }
+
+        draft.build(self)
+    }
+}
+
+// Can `from` derive a sentential form beginning with `to`?
+fn left_reaches(alternatives: &BTreeMap<String, Vec<Alternative>>, from: &str, to: &str) -> bool {
+    let mut visited = BTreeSet::new();
+    let mut stack = vec![from];
+    while let Some(name) = stack.pop() {
+        if !visited.insert(name) {
+            continue;
+        }
+        for alternative in alternatives.get(name).into_iter().flatten() {
+            if let Some(first) = alternative.rhs.first() {
+                if first == to {
+                    return true;
+                }
+                stack.push(first);
+            }
+        }
+    }
+    false
+}
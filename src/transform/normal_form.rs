@@ -0,0 +1,173 @@
+use std::collections::BTreeMap;
+
+use super::*;
+
+impl Grammar {
+    /// Convert the grammar to Chomsky normal form.
+    ///
+    /// Every rule of the result has the form `A -> B C` or `A -> a`,
+    /// apart from the start rule `S -> X`.
+    /// When the language contains the empty string, `X` also has the rule `X ->`, and appears on no RHS.
+    ///
+    /// The conversion removes the ε-rules, then the unit rules, then the useless symbols.
+    /// Next, each terminal `a` in a longer RHS is replaced by a new nonterminal `a_term` with the rule `a_term -> a`.
+    /// Finally, each rule `A -> X1 X2 ... Xn` with `n > 2` is split into
+    /// `A -> X1 A_part`, `A_part -> X2 A_part2`, ..., `A_partN -> Xn-1 Xn`.
+    pub fn to_cnf(&self) -> Transformed<'_> {
+        self.without_epsilon_rules()
+            .then(Grammar::without_unit_rules)
+            .then(Grammar::reduced)
+            .then(|grammar| grammar.separate_terminals(0))
+            .then(Grammar::binarized)
+    }
+
+    /// Convert the grammar to Greibach normal form.
+    ///
+    /// Every rule of the result has the form `A -> a B1 ... Bn` with `n >= 0`,
+    /// apart from the start rule `S -> X`.
+    /// When the language contains the empty string, `X` also has the rule `X ->`, and appears on no RHS.
+    ///
+    /// The conversion starts from Chomsky normal form and removes the left recursion.
+    /// The ε-rules and unit rules this introduces are removed again.
+    /// The nonterminals at the start of each RHS are then substituted away,
+    /// and each terminal after the first is replaced by a new nonterminal `a_term` with the rule `a_term -> a`.
+    /// Finally, the symbols left unused by the substitution are removed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the start symbol appears on the RHS of any rule.
+    pub fn to_gnf(&self) -> Transformed<'_> {
+        let start_symbol = self.start_symbol();
+        assert!(
+            self.rules().iter().all(|rule| !rule.rhs().contains(&start_symbol)),
+            "The start symbol must not appear on the RHS of any rule",
+        );
+
+        self.to_cnf()
+            .then(Grammar::without_left_recursion)
+            .then(Grammar::without_epsilon_rules)
+            .then(Grammar::without_unit_rules)
+            .then(Grammar::with_terminals_first)
+            .then(|grammar| grammar.separate_terminals(1))
+            .then(Grammar::reduced)
+    }
+
+    // Replace each terminal `a` at or after `from` in a RHS of length at least 2 with a new nonterminal `a_term`.
+    fn separate_terminals(&self, from: usize) -> Transformed<'_> {
+        let mut draft = Draft::new(self);
+        let mut terms: BTreeMap<Symbol<'_>, String> = BTreeMap::new();
+        let mut rules = vec![];
+
+        for rule in self.rules() {
+            let len = rule.rhs().len();
+            let mut rhs = vec![];
+            for (position, symbol) in rule.rhs().into_iter().enumerate() {
+                if symbol.is_terminal() && position >= from && len >= 2 {
+                    let term = terms.entry(symbol).or_insert_with(|| draft.fresh(&symbol.name(), "term"));
+                    rhs.push(term.clone());
+                } else {
+                    rhs.push(symbol.name());
+                }
+            }
+            rules.push((rule.lhs().name(), rhs, Provenance::of(rule)));
+        }
+
+        for (lhs, rhs, provenance) in rules {
+            draft.rule(lhs, rhs, provenance);
+        }
+        for (terminal, term) in terms {
+            draft.rule(term, vec![terminal.name()], Provenance::none());
+        }
+        draft.build(self)
+    }
+
+    // Split each rule with more than 2 symbols on the RHS into a chain of rules with 2.
+    fn binarized(&self) -> Transformed<'_> {
+        let mut draft = Draft::new(self);
+        let mut parts = vec![];
+
+        for rule in self.rules() {
+            let rhs: Vec<String> = rule.rhs().iter().map(|symbol| symbol.name()).collect();
+            if rhs.len() <= 2 {
+                draft.rule(rule.lhs().name(), rhs, Provenance::of(rule));
+                continue;
+            }
+
+            // The rule is only reduced once every symbol of the chain is on the stack.
+            let mut lhs = rule.lhs().name();
+            for symbol in &rhs[..rhs.len() - 2] {
+                let part = draft.fresh(&rule.lhs().name(), "part");
+                parts.push((lhs, vec![symbol.clone(), part.clone()], Provenance::none()));
+                lhs = part;
+            }
+            parts.push((lhs, rhs[rhs.len() - 2..].to_vec(), Provenance::of(rule).without_prefix(rhs.len() - 2)));
+        }
+
+        for (lhs, rhs, provenance) in parts {
+            draft.rule(lhs, rhs, provenance);
+        }
+        draft.build(self)
+    }
+
+    // Substitute the nonterminal at the start of each RHS until every RHS starts with a terminal.
+    // The grammar must not be left-recursive, and only the RHS of the start rule may be nullable.
+    fn with_terminals_first(&self) -> Transformed<'_> {
+        let (order, mut alternatives) = Draft::alternatives(self);
+        let start_symbol = self.start_symbol().name();
+
+        // Leave the start rule out while substituting, and put it back at the end.
+        let start_rule = alternatives.get_mut(&start_symbol).unwrap().remove(0);
+
+        let mut done = BTreeSet::new();
+        for name in &order {
+            substitute_first(name, &mut alternatives, &mut done, &mut vec![]);
+        }
+
+        let mut draft = Draft::new(self);
+        draft.alternative(&start_symbol, &start_rule);
+        for name in &order {
+            for alternative in &alternatives[name] {
+                draft.alternative(name, alternative);
+            }
+        }
+        draft.build(self)
+    }
+}
+
+fn substitute_first(
+    name: &str,
+    alternatives: &mut BTreeMap<String, Vec<Alternative>>,
+    done: &mut BTreeSet<String>,
+    path: &mut Vec<String>,
+) {
+    if done.contains(name) {
+        return;
+    }
+    assert!(!path.iter().any(|earlier| earlier == name), "Grammar is left-recursive: {name}");
+    path.push(name.to_string());
+
+    let mut substituted = vec![];
+    for alternative in alternatives[name].clone() {
+        let first = match alternative.rhs.first() {
+            Some(first) if alternatives.contains_key(first) => first.clone(),
+            _ => {
+                substituted.push(alternative);
+                continue;
+            }
+        };
+
+        substitute_first(&first, alternatives, done, path);
+        for inner in &alternatives[&first] {
+            let mut rhs = inner.rhs.clone();
+            rhs.extend(alternative.rhs[1..].iter().cloned());
+            substituted.push(Alternative {
+                rhs,
+                provenance: alternative.provenance.substitute(0, &inner.provenance, inner.rhs.len()),
+            });
+        }
+    }
+
+    alternatives.insert(name.to_string(), substituted);
+    path.pop();
+    done.insert(name.to_string());
+}
@@ -0,0 +1,63 @@
+use super::*;
+
+impl Grammar {
+    /// Remove the useless symbols, along with the rules which mention them.
+    ///
+    /// First, the rules which mention an unproductive nonterminal are removed.
+    /// Then, the symbols which can no longer be reached from the start symbol are removed.
+    ///
+    /// The start rule `S -> X` is always kept.
+    /// When the language of the grammar is empty, `X` is unproductive,
+    /// and it is given the single rule `X -> X` so that it remains a nonterminal.
+    pub fn reduced(&self) -> Transformed<'_> {
+        let analysis = GrammarAnalysis::build(self);
+        let unproductive = analysis.unproductive();
+
+        let productive_rules: Vec<Rule<'_>> = self
+            .rules()
+            .into_iter()
+            .filter(|rule| rule.rhs().iter().all(|symbol| !unproductive.contains(symbol)))
+            .collect();
+
+        let mut reachable = BTreeSet::new();
+        let mut queue = vec![self.start_symbol()];
+        while let Some(symbol) = queue.pop() {
+            if reachable.insert(symbol) {
+                for rule in &productive_rules {
+                    if rule.lhs() == symbol {
+                        queue.extend(rule.rhs());
+                    }
+                }
+            }
+        }
+
+        let start_rule = self.start_rule();
+        reachable.extend(start_rule.rhs());
+
+        let symbols = self
+            .symbols()
+            .into_iter()
+            .filter(|symbol| reachable.contains(symbol))
+            .map(|symbol| symbol.name())
+            .collect();
+        let mut draft = Draft {
+            symbols,
+            rules: vec![],
+        };
+
+        draft.rule(start_rule.lhs().name(), vec![start_rule.rhs()[0].name()], Provenance::of(start_rule));
+        for rule in productive_rules {
+            if !rule.is_start_rule() && reachable.contains(&rule.lhs()) {
+                let rhs = rule.rhs().iter().map(|symbol| symbol.name()).collect();
+                draft.rule(rule.lhs().name(), rhs, Provenance::of(rule));
+            }
+        }
+
+        let start_rhs = start_rule.rhs()[0];
+        if unproductive.contains(&start_rhs) {
+            draft.rule(start_rhs.name(), vec![start_rhs.name()], Provenance::none());
+        }
+
+        draft.build(self)
+    }
+}
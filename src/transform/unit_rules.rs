@@ -0,0 +1,75 @@
+use std::collections::VecDeque;
+
+use super::*;
+
+impl Grammar {
+    /// Remove the unit rules, which have a single nonterminal on the RHS, without changing the language.
+    ///
+    /// Whenever `A` derives `B` by a chain of unit rules,
+    /// each rule `B -> α` which is not itself a unit rule is copied to `A -> α`.
+    /// When the same RHS is reached by more than one chain, only the shortest chain is kept.
+    ///
+    /// The start rule is kept as it is.
+    /// Nonterminals which were only reachable through unit rules are left in the grammar.
+    /// Use `reduced` to remove them.
+    pub fn without_unit_rules(&self) -> Transformed<'_> {
+        let (order, _alternatives) = Draft::alternatives(self);
+        let mut draft = Draft::new(self);
+
+        for name in order {
+            let nonterminal = self.symbol(&name).unwrap();
+
+            // The nonterminals reachable from this one by unit rules,
+            // together with the reductions for the chain of unit rules, innermost first.
+            let mut chains: Vec<(Symbol<'_>, Vec<Rule<'_>>)> = vec![];
+            let mut queue = VecDeque::from([(nonterminal, vec![])]);
+            while let Some((symbol, chain)) = queue.pop_front() {
+                if chains.iter().any(|(reached, _chain)| *reached == symbol) {
+                    continue;
+                }
+                for rule in symbol.rules() {
+                    if is_unit_rule(rule) && !rule.is_start_rule() {
+                        let mut chain = chain.clone();
+                        chain.insert(0, rule);
+                        queue.push_back((rule.rhs()[0], chain));
+                    }
+                }
+                chains.push((symbol, chain));
+            }
+
+            let mut seen = vec![];
+            for (symbol, chain) in chains {
+                for rule in symbol.rules() {
+                    if rule.is_start_rule() {
+                        if symbol == nonterminal {
+                            draft.rule(name.clone(), vec![rule.rhs()[0].name()], Provenance::of(rule));
+                        }
+                        continue;
+                    }
+                    if is_unit_rule(rule) {
+                        continue;
+                    }
+
+                    let rhs: Vec<String> = rule.rhs().iter().map(|symbol| symbol.name()).collect();
+                    if seen.contains(&rhs) {
+                        continue;
+                    }
+                    seen.push(rhs.clone());
+
+                    let mut provenance = Provenance::of(rule);
+                    for unit_rule in &chain {
+                        provenance = Provenance::of(*unit_rule).substitute(0, &provenance, rhs.len());
+                    }
+                    draft.rule(name.clone(), rhs, provenance);
+                }
+            }
+        }
+
+        draft.build(self)
+    }
+}
+
+fn is_unit_rule(rule: Rule<'_>) -> bool {
+    let rhs = rule.rhs();
+    rhs.len() == 1 && rhs[0].is_nonterminal()
+}
@@ -0,0 +1,92 @@
+use super::*;
+
+/// A parse tree for a `Grammar`.
+///
+/// Each interior node is an application of a rule,
+/// with one child for each symbol on the rule's RHS.
+/// Each leaf is a terminal.
+#[derive(Clone, PartialEq, Eq)]
+pub enum ParseTree<'g> {
+    /// A terminal symbol.
+    Token(Symbol<'g>),
+
+    /// An application of a rule to its children.
+    Node(Rule<'g>, Vec<ParseTree<'g>>),
+}
+
+impl<'g> ParseTree<'g> {
+    /// The symbol at the root of this tree.
+    ///
+    /// For a `Node`, this is the LHS of the rule.
+    pub fn symbol(&self) -> Symbol<'g> {
+        match self {
+            ParseTree::Token(symbol) => *symbol,
+            ParseTree::Node(rule, _children) => rule.lhs(),
+        }
+    }
+
+    /// The rule at the root of this tree, if it is a `Node`.
+    pub fn rule(&self) -> Option<Rule<'g>> {
+        match self {
+            ParseTree::Token(_symbol) => None,
+            ParseTree::Node(rule, _children) => Some(*rule),
+        }
+    }
+
+    /// The children of the root of this tree.
+    pub fn children(&self) -> &[ParseTree<'g>] {
+        match self {
+            ParseTree::Token(_symbol) => &[],
+            ParseTree::Node(_rule, children) => children,
+        }
+    }
+
+    /// The terminals at the leaves of this tree, from left to right.
+    pub fn leaves(&self) -> Vec<Symbol<'g>> {
+        let mut leaves = vec![];
+        self.collect_leaves(&mut leaves);
+        leaves
+    }
+
+    fn collect_leaves(&self, leaves: &mut Vec<Symbol<'g>>) {
+        match self {
+            ParseTree::Token(symbol) => leaves.push(*symbol),
+            ParseTree::Node(_rule, children) => {
+                for child in children {
+                    child.collect_leaves(leaves);
+                }
+            }
+        }
+    }
+
+    /// The rules used in this tree, in the order of a leftmost derivation.
+    pub fn rules(&self) -> Vec<Rule<'g>> {
+        let mut rules = vec![];
+        self.collect_rules(&mut rules);
+        rules
+    }
+
+    fn collect_rules(&self, rules: &mut Vec<Rule<'g>>) {
+        if let ParseTree::Node(rule, children) = self {
+            rules.push(*rule);
+            for child in children {
+                child.collect_rules(rules);
+            }
+        }
+    }
+}
+
+impl<'g> std::fmt::Debug for ParseTree<'g> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseTree::Token(symbol) => write!(f, "{symbol:?}"),
+            ParseTree::Node(rule, children) => {
+                write!(f, "({:?}", rule.lhs())?;
+                for child in children {
+                    write!(f, " {child:?}")?;
+                }
+                write!(f, ")")
+            }
+        }
+    }
+}